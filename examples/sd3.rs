@@ -37,25 +37,10 @@ async fn main() {
     while let Some(event) = prediction.next().await {
         let event = event.unwrap();
         match event {
-            gradio::structs::QueueDataMessage::Estimation {
-                rank, queue_size, ..
-            } => {
-                println!("Queueing: {}/{}", rank + 1, queue_size);
-            }
-            gradio::structs::QueueDataMessage::Progress { progress_data, .. } => {
-                if progress_data.is_none() {
-                    continue;
-                }
-                let progress_data = progress_data.unwrap();
-                if !progress_data.is_empty() {
-                    let progress_data = &progress_data[0];
-                    println!(
-                        "Processing: {}/{} {}",
-                        progress_data.index + 1,
-                        progress_data.length.unwrap(),
-                        progress_data.unit
-                    );
-                }
+            gradio::structs::QueueDataMessage::Estimation { .. }
+            | gradio::structs::QueueDataMessage::ProcessStarts { .. }
+            | gradio::structs::QueueDataMessage::Progress { .. } => {
+                println!("{}", prediction.progress_state());
             }
             gradio::structs::QueueDataMessage::ProcessCompleted { output, .. } => {
                 let output: Vec<PredictionOutput> = output.try_into().unwrap();
@@ -0,0 +1,108 @@
+//! Procedural macros for the [`gradio`](https://docs.rs/gradio) crate, use them
+//! through its re-exports.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, Index, LitStr, Type};
+
+/// Declare a struct as the inputs of an endpoint.
+///
+/// Fields are sent in declaration order, so they must follow the parameters of
+/// the endpoint. Each field type must implement `gradio::IntoPredictionInput`,
+/// paths are uploaded as files. `output` is the type the outputs are decoded
+/// into, in order, and defaults to `Vec<gradio::PredictionOutput>`.
+///
+/// ```ignore
+/// #[gradio::endpoint(route = "/predict", output = (String,))]
+/// struct Greet {
+///     name: String,
+/// }
+///
+/// let (greeting,) = client.call(Greet { name: "Rust".into() }).await?;
+/// ```
+#[proc_macro_attribute]
+pub fn endpoint(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut route: Option<LitStr> = None;
+    let mut output: Option<Type> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("route") {
+            route = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("output") {
+            output = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported endpoint property, expected `route` or `output`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+
+    let input = parse_macro_input!(item as DeriveInput);
+    let expanded = match route {
+        Some(route) => expand(&input, &route, output),
+        None => Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "missing `route`, as in #[gradio::endpoint(route = \"/predict\")]",
+        )),
+    };
+    let expanded = expanded.unwrap_or_else(syn::Error::into_compile_error);
+    quote!(#input #expanded).into()
+}
+
+fn expand(input: &DeriveInput, route: &LitStr, output: Option<Type>) -> syn::Result<TokenStream2> {
+    let route_value = route.value();
+    let route_value = route_value.trim_start_matches('/');
+    if route_value.is_empty() {
+        return Err(syn::Error::new(route.span(), "route must not be empty"));
+    }
+    let route = LitStr::new(&format!("/{}", route_value), route.span());
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "#[gradio::endpoint] only supports structs",
+        ));
+    };
+    let inputs = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let name = &field.ident;
+                quote_spanned!(field.ty.span()=>
+                    ::gradio::IntoPredictionInput::into_prediction_input(self.#name)
+                )
+            })
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let index = Index::from(i);
+                quote_spanned!(field.ty.span()=>
+                    ::gradio::IntoPredictionInput::into_prediction_input(self.#index)
+                )
+            })
+            .collect(),
+        Fields::Unit => vec![],
+    };
+
+    let output = output
+        .map(|output| quote!(#output))
+        .unwrap_or_else(|| quote!(::std::vec::Vec<::gradio::PredictionOutput>));
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::gradio::Endpoint for #name #ty_generics #where_clause {
+            const ROUTE: &'static str = #route;
+            type Output = #output;
+
+            fn into_inputs(self) -> ::std::vec::Vec<::gradio::PredictionInput> {
+                ::std::vec![#(#inputs),*]
+            }
+        }
+    })
+}
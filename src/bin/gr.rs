@@ -2,7 +2,20 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use clap::{arg, Command};
-use gradio::{Client, ClientOptions, PredictionInput, PredictionOutput};
+use gradio::{
+    codegen::{generate, CodegenOptions},
+    drift::ApiLock,
+    mcp::McpServer,
+    openapi::{to_openapi, OpenApiOptions},
+    structs::ApiInfo,
+    Client, ClientOptions, PredictionInput, PredictionOutput, SavedApi,
+};
+
+struct RunOptions<'a> {
+    token: Option<&'a String>,
+    outdir: Option<&'a String>,
+    record: Option<&'a String>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -19,12 +32,64 @@ async fn main() -> Result<()> {
                 .get_many::<String>("options")
                 .unwrap_or_default()
                 .collect();
-            run_command(space_id, route, options, token, output).await?;
+            let run_options = RunOptions {
+                token,
+                outdir: output,
+                record: sub_matches.get_one::<String>("record"),
+            };
+            run_command(space_id, route, options, run_options).await?;
         }
         Some(("list", sub_matches)) => {
             let space_id = sub_matches.get_one::<String>("space_id").expect("required");
             list_command(space_id, token).await?;
         }
+        Some(("codegen", sub_matches)) => {
+            let app = sub_matches.get_one::<String>("app").expect("required");
+            codegen_command(
+                app,
+                sub_matches.get_one::<String>("app_reference"),
+                sub_matches.get_one::<String>("name"),
+                sub_matches.get_one::<String>("out"),
+                token,
+            )
+            .await?;
+        }
+        Some(("openapi", sub_matches)) => {
+            let app = sub_matches.get_one::<String>("app").expect("required");
+            let app_reference = sub_matches.get_one::<String>("app_reference");
+            openapi_command(
+                app,
+                app_reference,
+                sub_matches.get_one::<String>("out"),
+                token,
+            )
+            .await?;
+        }
+        Some(("lock", sub_matches)) => {
+            let app = sub_matches.get_one::<String>("app").expect("required");
+            let out = sub_matches.get_one::<String>("out").expect("default");
+            let app_reference = sub_matches.get_one::<String>("app_reference");
+            lock_command(app, app_reference, out, token).await?;
+        }
+        Some(("diff", sub_matches)) => {
+            let lock = sub_matches.get_one::<String>("lock").expect("default");
+            diff_command(lock, sub_matches.get_one::<String>("app"), token).await?;
+        }
+        Some(("mcp", sub_matches)) => {
+            let space_id = sub_matches.get_one::<String>("space_id").expect("required");
+            let allowed_dirs: Vec<&String> = sub_matches
+                .get_many::<String>("allow-dir")
+                .unwrap_or_default()
+                .collect();
+            mcp_command(space_id, allowed_dirs, token).await?;
+        }
+        #[cfg(feature = "serve")]
+        Some(("serve", sub_matches)) => {
+            let space_id = sub_matches.get_one::<String>("space_id").expect("required");
+            let route = sub_matches.get_one::<String>("route").expect("default");
+            let address = sub_matches.get_one::<String>("address").expect("default");
+            serve_command(space_id, route, address, token).await?;
+        }
         _ => {
             cli().print_help()?;
         }
@@ -34,7 +99,7 @@ async fn main() -> Result<()> {
 }
 
 fn cli() -> Command {
-    Command::new("gr")
+    let command = Command::new("gr")
         .version(env!("CARGO_PKG_VERSION"))
         .about("Gradio Command Line Client")
         .arg(arg!(-t --token <token> "The Hugging Face Access Token"))
@@ -47,6 +112,7 @@ fn cli() -> Command {
                 .about("Perform a prediction")
                 .arg(arg!(<space_id> "The ID of the Gradio space"))
                 .arg(arg!(<route> "The route to run"))
+                .arg(arg!(--record <path> "Save the traffic of the run as a replayable cassette"))
                 .arg(arg!([options]... "Options for the run command")),
         )
         .subcommand(
@@ -55,22 +121,78 @@ fn cli() -> Command {
                 .about("List routes in a Gradio app")
                 .arg(arg!(<space_id> "The ID of the Gradio space")),
         )
+        .subcommand(
+            Command::new("codegen")
+                .about("Generate a typed Rust client for a Gradio app")
+                .arg(arg!(<app> "The ID of the Gradio space, or a saved API info or snapshot JSON file"))
+                .arg(arg!(app_reference: --app <reference> "The Space ID or URL of the app, for JSON files that do not name it"))
+                .arg(arg!(--name <name> "Name of the generated client struct"))
+                .arg(arg!(--out <path> "Write the module to this file instead of stdout")),
+        )
+        .subcommand(
+            Command::new("openapi")
+                .about("Export the API of a Gradio app as an OpenAPI 3.1 document")
+                .arg(arg!(<app> "The ID of the Gradio space, or a saved API info or snapshot JSON file"))
+                .arg(arg!(app_reference: --app <reference> "The Space ID or URL of the app, for JSON files that do not name it"))
+                .arg(arg!(--out <path> "Write the document to this file instead of stdout")),
+        )
+        .subcommand(
+            Command::new("lock")
+                .about("Save the API of a Gradio app to a lock file to detect later changes")
+                .arg(arg!(<app> "The ID of the Gradio space, or a saved API info or snapshot JSON file"))
+                .arg(arg!(app_reference: --app <reference> "The Space ID or URL of the app, for JSON files that do not name it"))
+                .arg(arg!(--out <path> "The lock file to write").default_value("gradio.lock.json")),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compare the API of a Gradio app against a lock file, fails on breaking changes")
+                .arg(arg!([app] "The ID of the Gradio space or a JSON file, defaults to the locked app"))
+                .arg(arg!(--lock <path> "The lock file to compare against").default_value("gradio.lock.json")),
+        )
+        .subcommand(
+            Command::new("mcp")
+                .about("Run an MCP server on stdio with the routes of a Gradio app as tools")
+                .arg(arg!(<space_id> "The ID of the Gradio space"))
+                .arg(
+                    arg!(--"allow-dir" <dir> "Let file parameters read local files in this directory, otherwise only URLs are accepted")
+                        .action(clap::ArgAction::Append),
+                ),
+        );
+
+    #[cfg(feature = "serve")]
+    let command = command.subcommand(
+        Command::new("serve")
+            .about("Serve a chat app as an OpenAI-compatible chat completions API")
+            .arg(arg!(<space_id> "The ID of the Gradio space"))
+            .arg(arg!(-r --route <route> "The chat endpoint to call").default_value("/chat"))
+            .arg(
+                arg!(-a --address <address> "The address to listen on")
+                    .default_value("127.0.0.1:8000"),
+            ),
+    );
+
+    command
 }
 
 async fn run_command(
     space_id: &str,
     route: &str,
     options: Vec<&String>,
-    token: Option<&String>,
-    outdir: Option<&String>,
+    run_options: RunOptions<'_>,
 ) -> Result<()> {
+    let RunOptions {
+        token,
+        outdir,
+        record,
+    } = run_options;
     let route = format!("/{}", route.trim_start_matches('/'));
 
-    let opt = if let Some(token) = token {
+    let mut opt = if let Some(token) = token {
         ClientOptions::with_hf_token(token.clone())
     } else {
         ClientOptions::default()
     };
+    opt.record = record.is_some();
 
     let client = Client::new(space_id, opt).await?;
 
@@ -123,24 +245,14 @@ async fn run_command(
     while let Some(event) = prediction.next().await {
         let event = event?;
         match event {
-            gradio::structs::QueueDataMessage::Estimation {
-                rank, queue_size, ..
-            } => {
-                eprintln!("Queueing: {}/{}", rank + 1, queue_size);
+            gradio::structs::QueueDataMessage::Estimation { .. }
+            | gradio::structs::QueueDataMessage::ProcessStarts { .. }
+            | gradio::structs::QueueDataMessage::Progress { .. } => {
+                eprintln!("{}", prediction.progress_state());
             }
-            gradio::structs::QueueDataMessage::Progress { progress_data, .. } => {
-                if progress_data.is_none() {
-                    continue;
-                }
-                let progress_data = progress_data.unwrap();
-                if !progress_data.is_empty() {
-                    let progress_data = &progress_data[0];
-                    eprintln!(
-                        "Processing: {}/{} {}",
-                        progress_data.index + 1,
-                        progress_data.length.unwrap(),
-                        progress_data.unit
-                    );
+            gradio::structs::QueueDataMessage::Log { .. } => {
+                if let Some(log) = event.as_log() {
+                    eprintln!("{:?}: {}", log.level, log.log);
                 }
             }
             gradio::structs::QueueDataMessage::ProcessCompleted { output, .. } => {
@@ -182,6 +294,11 @@ async fn run_command(
         }
     }
 
+    if let (Some(path), Some(cassette)) = (record, client.cassette()) {
+        cassette.save(path).await?;
+        eprintln!("Recorded cassette to {}", path);
+    }
+
     Ok(())
 }
 
@@ -234,3 +351,206 @@ async fn list_command(space_id: &str, token: Option<&String>) -> Result<()> {
 
     Ok(())
 }
+
+async fn codegen_command(
+    app: &str,
+    app_reference: Option<&String>,
+    name: Option<&String>,
+    out: Option<&String>,
+    token: Option<&String>,
+) -> Result<()> {
+    let AppApi {
+        api_info,
+        app_reference,
+        ..
+    } = load_api(app, app_reference, token).await?;
+
+    let mut options = CodegenOptions::new(app_reference);
+    if let Some(name) = name {
+        options = options.with_struct_name(name);
+    }
+    let source = generate(&api_info, &options);
+
+    if let Some(out) = out {
+        std::fs::write(out, source)?;
+        eprintln!("Generated {} in {}", options.struct_name, out);
+    } else {
+        print!("{}", source);
+    }
+
+    Ok(())
+}
+
+async fn openapi_command(
+    app: &str,
+    app_reference: Option<&String>,
+    out: Option<&String>,
+    token: Option<&String>,
+) -> Result<()> {
+    let app_api = load_api(app, app_reference, token).await?;
+
+    let mut options = OpenApiOptions::new(&app_api.app_reference);
+    if let Some(version) = app_api.version {
+        options = options.with_version(version);
+    }
+    if let Some(api_root) = app_api.api_root {
+        options = options.with_server_url(api_root);
+    }
+    let document = serde_json::to_string_pretty(&to_openapi(&app_api.api_info, &options))?;
+
+    if let Some(out) = out {
+        std::fs::write(out, document)?;
+        eprintln!("Wrote OpenAPI document to {}", out);
+    } else {
+        println!("{}", document);
+    }
+
+    Ok(())
+}
+
+async fn lock_command(
+    app: &str,
+    app_reference: Option<&String>,
+    out: &str,
+    token: Option<&String>,
+) -> Result<()> {
+    let app_api = load_api(app, app_reference, token).await?;
+    let lock = ApiLock::new(
+        app_api.app_reference,
+        app_api.version.unwrap_or_default(),
+        app_api.api_info,
+    );
+    lock.save(out).await?;
+    eprintln!(
+        "Locked {} routes of {} to {}",
+        lock.api_info.named_endpoints.len(),
+        lock.app_reference,
+        out
+    );
+
+    Ok(())
+}
+
+async fn diff_command(lock: &str, app: Option<&String>, token: Option<&String>) -> Result<()> {
+    let lock = ApiLock::load(lock).await?;
+    let app = app.unwrap_or(&lock.app_reference);
+    let current = load_api(app, Some(&lock.app_reference), token).await?;
+
+    let diff = lock.diff(&current.api_info);
+    if diff.is_empty() {
+        println!("No API changes in {}", app);
+        return Ok(());
+    }
+    for change in &diff.changes {
+        let kind = if change.is_breaking() {
+            "breaking"
+        } else {
+            "compatible"
+        };
+        println!("{:10} {}", kind, change);
+    }
+
+    if diff.is_breaking() {
+        eprintln!(
+            "{} breaking changes in {} since it was locked",
+            diff.breaking().count(),
+            app
+        );
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// The API of an app, with what is known about where it is served
+struct AppApi {
+    api_info: ApiInfo,
+    app_reference: String,
+    api_root: Option<String>,
+    version: Option<String>,
+}
+
+/// Fetch the API of a live app, or read a saved API info, snapshot, cassette or
+/// lock file, which must name the app unless `app_reference` is given
+async fn load_api(
+    app: &str,
+    app_reference: Option<&String>,
+    token: Option<&String>,
+) -> Result<AppApi> {
+    if app.ends_with(".json") {
+        let saved = SavedApi::load(app)?;
+        let app_reference = app_reference
+            .cloned()
+            .or(saved.app_reference)
+            .ok_or_else(|| anyhow::anyhow!("{} does not name its app, pass it with --app", app))?;
+        return Ok(AppApi {
+            api_info: saved.api_info,
+            app_reference,
+            api_root: saved.api_root,
+            version: saved.version,
+        });
+    }
+
+    let opt = if let Some(token) = token {
+        ClientOptions::with_hf_token(token.clone())
+    } else {
+        ClientOptions::default()
+    };
+    let client = Client::new(app, opt).await?;
+    Ok(AppApi {
+        api_info: client.view_api(),
+        app_reference: app_reference.cloned().unwrap_or_else(|| app.to_string()),
+        api_root: Some(client.api_root.clone()),
+        version: Some(client.view_config().version),
+    })
+}
+
+async fn mcp_command(
+    space_id: &str,
+    allowed_dirs: Vec<&String>,
+    token: Option<&String>,
+) -> Result<()> {
+    let opt = if let Some(token) = token {
+        ClientOptions::with_hf_token(token.clone())
+    } else {
+        ClientOptions::default()
+    };
+
+    let client = Client::new(space_id, opt).await?;
+    let server = allowed_dirs
+        .into_iter()
+        .fold(McpServer::new(client, space_id), |server, dir| {
+            server.with_allowed_dir(dir)
+        });
+    server.serve_stdio().await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "serve")]
+async fn serve_command(
+    space_id: &str,
+    route: &str,
+    address: &str,
+    token: Option<&String>,
+) -> Result<()> {
+    let opt = if let Some(token) = token {
+        ClientOptions::with_hf_token(token.clone())
+    } else {
+        ClientOptions::default()
+    };
+
+    let client = Client::new(space_id, opt).await?;
+    let options = gradio::gateway::GatewayOptions {
+        route: format!("/{}", route.trim_start_matches('/')),
+        model: space_id.to_string(),
+    };
+
+    eprintln!(
+        "Serving {} at http://{}/v1/chat/completions",
+        space_id, address
+    );
+    gradio::gateway::serve(client, options, address).await?;
+
+    Ok(())
+}
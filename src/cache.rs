@@ -0,0 +1,359 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    data::{GradioFileData, PredictionInput, PredictionOutput},
+    Result,
+};
+
+const ENTRY_FILE: &str = "entry.json";
+
+#[derive(Clone, Debug)]
+pub struct CacheOptions {
+    /// Directory holding the cached entries
+    pub dir: PathBuf,
+    /// Entries older than this are ignored and removed
+    pub ttl: Option<Duration>,
+    /// Oldest entries are evicted once the cache grows beyond this many bytes
+    pub max_size: Option<u64>,
+}
+
+impl CacheOptions {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: None,
+            max_size: None,
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CacheEntry {
+    created_at: u64,
+    outputs: Vec<PredictionOutput>,
+}
+
+/// On-disk cache of prediction outputs for deterministic endpoints.
+///
+/// Entries are keyed by app reference, route, app version and a hash of the
+/// inputs, where files are hashed by content. File outputs are downloaded into
+/// the cache and returned with a `file://` URL, and their `download` reads the
+/// cached copy.
+#[derive(Debug)]
+pub struct ResultCache {
+    options: CacheOptions,
+}
+
+impl ResultCache {
+    pub fn new(options: CacheOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &CacheOptions {
+        &self.options
+    }
+
+    /// Compute the cache key of a prediction
+    pub async fn key(
+        app_reference: &str,
+        route: &str,
+        version: &str,
+        inputs: &[PredictionInput],
+    ) -> Result<String> {
+        let mut hashed = vec![];
+        for input in inputs {
+            hashed.push(hash_input(input).await?);
+        }
+        let material = serde_json::json!({
+            "app": app_reference,
+            "route": format!("/{}", route.trim_start_matches('/')),
+            "version": version,
+            "inputs": hashed,
+        });
+        Ok(sha256_hex(material.to_string().as_bytes()))
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<PredictionOutput>>> {
+        let path = self.options.dir.join(key).join(ENTRY_FILE);
+        let content = match tokio::fs::read(&path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let entry: CacheEntry = serde_json::from_slice(&content)?;
+
+        let entry_dir = self.options.dir.join(key);
+        if self.is_expired(entry.created_at) {
+            tokio::fs::remove_dir_all(entry_dir).await?;
+            return Ok(None);
+        }
+        let outputs = entry
+            .outputs
+            .into_iter()
+            .map(|output| match output {
+                PredictionOutput::File(file) => PredictionOutput::File(rehydrate(file, &entry_dir)),
+                value => value,
+            })
+            .collect();
+        Ok(Some(outputs))
+    }
+
+    /// Store the outputs of a prediction, downloading file outputs into the cache
+    pub async fn put(
+        &self,
+        key: &str,
+        outputs: &[PredictionOutput],
+        http_client: &reqwest::Client,
+    ) -> Result<Vec<PredictionOutput>> {
+        let entry_dir = self.options.dir.join(key);
+        tokio::fs::create_dir_all(&entry_dir).await?;
+
+        let mut stored = Vec::with_capacity(outputs.len());
+        for (index, output) in outputs.iter().enumerate() {
+            match output {
+                PredictionOutput::File(file) => {
+                    let path = entry_dir.join(format!("{}.{}", index, file.suggest_extension()));
+                    file.save_to_path(&path, Some(http_client.clone())).await?;
+                    stored.push(PredictionOutput::File(local_file(file, &path)?));
+                }
+                PredictionOutput::Value(_) => stored.push(output.clone()),
+            }
+        }
+
+        let entry = CacheEntry {
+            created_at: now(),
+            outputs: stored.clone(),
+        };
+        tokio::fs::write(entry_dir.join(ENTRY_FILE), serde_json::to_vec(&entry)?).await?;
+        self.evict().await?;
+
+        Ok(stored)
+    }
+
+    /// Remove every entry
+    pub async fn clear(&self) -> Result<()> {
+        match tokio::fs::remove_dir_all(&self.options.dir).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Drop expired entries, then the oldest ones until the size limit is met
+    async fn evict(&self) -> Result<()> {
+        let mut entries = vec![];
+        let mut total = 0;
+        let mut dir = tokio::fs::read_dir(&self.options.dir).await?;
+        while let Some(item) = dir.next_entry().await? {
+            if !item.file_type().await?.is_dir() {
+                continue;
+            }
+            let path = item.path();
+            let created_at = match tokio::fs::read(path.join(ENTRY_FILE)).await {
+                Ok(content) => serde_json::from_slice::<CacheEntry>(&content)
+                    .map(|entry| entry.created_at)
+                    .unwrap_or(0),
+                Err(_) => 0,
+            };
+            if self.is_expired(created_at) {
+                tokio::fs::remove_dir_all(&path).await?;
+                continue;
+            }
+            let size = dir_size(&path).await?;
+            total += size;
+            entries.push((created_at, size, path));
+        }
+
+        let Some(max_size) = self.options.max_size else {
+            return Ok(());
+        };
+        entries.sort_by_key(|(created_at, _, _)| *created_at);
+        for (_, size, path) in entries {
+            if total <= max_size {
+                break;
+            }
+            tokio::fs::remove_dir_all(&path).await?;
+            total -= size;
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, created_at: u64) -> bool {
+        self.options
+            .ttl
+            .is_some_and(|ttl| now().saturating_sub(created_at) > ttl.as_secs())
+    }
+}
+
+fn hash_input(
+    input: &PredictionInput,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<serde_json::Value>> + Send + '_>> {
+    Box::pin(async move {
+        Ok(match input {
+            PredictionInput::Value(value) => value.clone(),
+            PredictionInput::File(path) => {
+                serde_json::json!({ "file": sha256_hex(&tokio::fs::read(path).await?) })
+            }
+            PredictionInput::Array(values) => {
+                let mut hashed = vec![];
+                for value in values {
+                    hashed.push(hash_input(value).await?);
+                }
+                serde_json::Value::Array(hashed)
+            }
+        })
+    })
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn local_file(file: &GradioFileData, path: &Path) -> Result<GradioFileData> {
+    let path = std::path::absolute(path)?;
+    let url = reqwest::Url::from_file_path(&path)
+        .map(String::from)
+        .map_err(|_| crate::Error::InvalidFilePath)?;
+    Ok(GradioFileData {
+        path: Some(path.to_string_lossy().to_string()),
+        url: Some(url),
+        cached_path: Some(path),
+        ..file.clone()
+    })
+}
+
+/// Point a cached file output at its copy in `entry_dir`, by file name only so
+/// the entry cannot refer to files outside of it
+fn rehydrate(file: GradioFileData, entry_dir: &Path) -> GradioFileData {
+    let cached_path = file
+        .path
+        .as_deref()
+        .and_then(|path| Path::new(path).file_name())
+        .map(|name| entry_dir.join(name));
+    GradioFileData {
+        cached_path,
+        ..file
+    }
+}
+
+fn dir_size(
+    path: &Path,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<u64>> + Send + '_>> {
+    Box::pin(async move {
+        let mut size = 0;
+        let mut dir = tokio::fs::read_dir(path).await?;
+        while let Some(item) = dir.next_entry().await? {
+            let metadata = item.metadata().await?;
+            if metadata.is_dir() {
+                size += dir_size(&item.path()).await?;
+            } else {
+                size += metadata.len();
+            }
+        }
+        Ok(size)
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{CacheOptions, ResultCache};
+    use crate::data::{PredictionInput, PredictionOutput};
+
+    #[tokio::test]
+    async fn key_depends_on_file_content_not_path() {
+        let dir = std::env::temp_dir().join(format!("gradio-cache-key-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let a = dir.join("a.txt");
+        let b = dir.join("b.txt");
+        tokio::fs::write(&a, "same").await.unwrap();
+        tokio::fs::write(&b, "same").await.unwrap();
+
+        let key_a = ResultCache::key(
+            "app",
+            "/predict",
+            "5.0.0",
+            &[PredictionInput::from_file(&a)],
+        )
+        .await
+        .unwrap();
+        let key_b = ResultCache::key("app", "predict", "5.0.0", &[PredictionInput::from_file(&b)])
+            .await
+            .unwrap();
+        let key_c = ResultCache::key(
+            "app",
+            "/predict",
+            "5.0.1",
+            &[PredictionInput::from_file(&b)],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(key_a, key_b);
+        assert_ne!(key_b, key_c);
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stores_values_and_evicts_by_size() {
+        let dir = std::env::temp_dir().join(format!("gradio-cache-evict-{}", std::process::id()));
+        let cache = ResultCache::new(CacheOptions::new(&dir).with_max_size(1));
+        let http_client = reqwest::Client::new();
+        let outputs = vec![PredictionOutput::Value(json!("Hello Rust!"))];
+
+        cache.put("first", &outputs, &http_client).await.unwrap();
+        assert!(cache.get("first").await.unwrap().is_none());
+
+        let cache = ResultCache::new(CacheOptions::new(&dir));
+        cache.put("second", &outputs, &http_client).await.unwrap();
+        let cached = cache.get("second").await.unwrap().unwrap();
+        assert_eq!(cached[0].clone().as_value().unwrap(), json!("Hello Rust!"));
+
+        cache.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cached_files_download_from_the_cache() {
+        let dir = std::env::temp_dir().join(format!("gradio-cache-files-{}", std::process::id()));
+        let cache = ResultCache::new(CacheOptions::new(&dir));
+        let entry_dir = dir.join("entry");
+        tokio::fs::create_dir_all(&entry_dir).await.unwrap();
+        tokio::fs::write(entry_dir.join("0.txt"), "cached")
+            .await
+            .unwrap();
+        let entry = json!({
+            "created_at": super::now(),
+            "outputs": [{"path": "/elsewhere/0.txt", "url": "file:///etc/passwd", "meta": {"_type": "gradio.FileData"}}]
+        });
+        tokio::fs::write(entry_dir.join(super::ENTRY_FILE), entry.to_string())
+            .await
+            .unwrap();
+
+        let cached = cache.get("entry").await.unwrap().unwrap();
+        let file = cached[0].clone().as_file().unwrap();
+        assert_eq!(file.download(None).await.unwrap(), "cached");
+
+        cache.clear().await.unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    structs::{ApiInfo, AppConfig},
+    Error, Result,
+};
+
+/// Raw `data` of the SSE frames received by a recording stream
+pub(crate) type FrameLog = Arc<Mutex<Vec<String>>>;
+
+/// Captured traffic of a [`Client`](crate::Client), replayable without network.
+///
+/// Record with [`ClientOptions::record`](crate::ClientOptions::record), take the
+/// cassette with [`Client::cassette`](crate::Client::cassette) and replay it with
+/// [`Client::from_cassette`](crate::Client::from_cassette).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Cassette {
+    pub api_root: String,
+    pub space_id: Option<String>,
+    pub config: AppConfig,
+    pub api_info: ApiInfo,
+    #[serde(default)]
+    pub uploads: Vec<RecordedUpload>,
+    #[serde(default)]
+    pub predictions: Vec<RecordedPrediction>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedUpload {
+    /// SHA-256 of the uploaded content
+    pub sha256: String,
+    /// The path the server stored the file at
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordedPrediction {
+    pub fn_index: i64,
+    /// The preprocessed inputs sent to `/queue/join`
+    pub data: Vec<serde_json::Value>,
+    pub event_id: String,
+    /// Raw `data` of every frame received from `/queue/data`, in order
+    pub frames: Vec<String>,
+}
+
+impl Cassette {
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = tokio::fs::read(path).await?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+/// Whether a client records its traffic or replays a cassette
+#[derive(Debug)]
+pub(crate) enum Tape {
+    Recording(Recorder),
+    Replaying(Replayer),
+}
+
+#[derive(Debug)]
+struct RecordingPrediction {
+    fn_index: i64,
+    data: Vec<serde_json::Value>,
+    event_id: String,
+    frames: FrameLog,
+}
+
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    /// The config as fetched, before any `gr.render` updates
+    config: Box<AppConfig>,
+    predictions: Mutex<Vec<RecordingPrediction>>,
+}
+
+impl Recorder {
+    pub(crate) fn new(config: AppConfig) -> Self {
+        Self {
+            config: Box::new(config),
+            predictions: Mutex::new(vec![]),
+        }
+    }
+
+    pub(crate) fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    /// Register a joined prediction, returns the log its frames go to
+    pub(crate) fn start(
+        &self,
+        fn_index: i64,
+        data: Vec<serde_json::Value>,
+        event_id: &str,
+    ) -> FrameLog {
+        let frames = FrameLog::default();
+        self.predictions
+            .lock()
+            .expect("recorder lock poisoned")
+            .push(RecordingPrediction {
+                fn_index,
+                data,
+                event_id: event_id.to_string(),
+                frames: frames.clone(),
+            });
+        frames
+    }
+
+    pub(crate) fn predictions(&self) -> Vec<RecordedPrediction> {
+        self.predictions
+            .lock()
+            .expect("recorder lock poisoned")
+            .iter()
+            .map(|prediction| RecordedPrediction {
+                fn_index: prediction.fn_index,
+                data: prediction.data.clone(),
+                event_id: prediction.event_id.clone(),
+                frames: prediction
+                    .frames
+                    .lock()
+                    .expect("frame log lock poisoned")
+                    .clone(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Replayer {
+    predictions: Mutex<Vec<Option<RecordedPrediction>>>,
+}
+
+impl Replayer {
+    pub(crate) fn new(predictions: Vec<RecordedPrediction>) -> Self {
+        Self {
+            predictions: Mutex::new(predictions.into_iter().map(Some).collect()),
+        }
+    }
+
+    /// Take the first unused prediction made with the same function and inputs
+    pub(crate) fn take(
+        &self,
+        fn_index: i64,
+        data: &[serde_json::Value],
+    ) -> Result<RecordedPrediction> {
+        let mut predictions = self.predictions.lock().expect("replayer lock poisoned");
+        predictions
+            .iter_mut()
+            .find(|slot| {
+                slot.as_ref().is_some_and(|prediction| {
+                    prediction.fn_index == fn_index
+                        && prediction.data.len() == data.len()
+                        && prediction
+                            .data
+                            .iter()
+                            .zip(data)
+                            .all(|(recorded, sent)| matches_input(recorded, sent))
+                })
+            })
+            .and_then(Option::take)
+            .ok_or_else(|| Error::CassetteMismatch {
+                message: format!(
+                    "no recorded prediction of fn_index {} with data {}",
+                    fn_index,
+                    serde_json::Value::from(data.to_vec())
+                ),
+            })
+    }
+}
+
+/// Compare inputs, leaving out the `mime_type` of uploaded files, which the client
+/// derives itself and which changes with the `sniff` feature
+fn matches_input(recorded: &serde_json::Value, sent: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (recorded, sent) {
+        (Value::Array(recorded), Value::Array(sent)) => {
+            recorded.len() == sent.len()
+                && recorded
+                    .iter()
+                    .zip(sent)
+                    .all(|(recorded, sent)| matches_input(recorded, sent))
+        }
+        (Value::Object(recorded), Value::Object(sent)) => {
+            let is_file = recorded
+                .get("meta")
+                .and_then(|meta| meta.get("_type"))
+                .is_some_and(|kind| kind == "gradio.FileData");
+            let keys = |object: &'_ serde_json::Map<String, Value>| {
+                object
+                    .keys()
+                    .filter(|key| !(is_file && *key == "mime_type"))
+                    .count()
+            };
+            keys(recorded) == keys(sent)
+                && recorded
+                    .iter()
+                    .filter(|(key, _)| !(is_file && *key == "mime_type"))
+                    .all(|(key, value)| {
+                        sent.get(key).is_some_and(|sent| matches_input(value, sent))
+                    })
+        }
+        _ => recorded == sent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{RecordedPrediction, Replayer};
+
+    fn prediction(data: serde_json::Value, event_id: &str) -> RecordedPrediction {
+        RecordedPrediction {
+            fn_index: 0,
+            data: vec![data],
+            event_id: event_id.to_string(),
+            frames: vec![],
+        }
+    }
+
+    #[test]
+    fn replays_matching_predictions_once_in_order() {
+        let replayer = Replayer::new(vec![
+            prediction(json!("a"), "first"),
+            prediction(json!("b"), "second"),
+            prediction(json!("a"), "third"),
+        ]);
+
+        assert_eq!(replayer.take(0, &[json!("a")]).unwrap().event_id, "first");
+        assert_eq!(replayer.take(0, &[json!("a")]).unwrap().event_id, "third");
+        assert!(replayer.take(0, &[json!("a")]).is_err());
+        assert!(replayer.take(1, &[json!("b")]).is_err());
+    }
+
+    #[test]
+    fn ignores_the_mime_type_of_uploaded_files() {
+        let file = |mime_type: &str| json!({"path": "a.wav", "mime_type": mime_type, "meta": {"_type": "gradio.FileData"}});
+        let replayer = Replayer::new(vec![
+            prediction(json!([file("audio/wav")]), "file"),
+            prediction(json!({"mime_type": "text/plain"}), "value"),
+        ]);
+
+        assert_eq!(
+            replayer
+                .take(0, &[json!([file("audio/x-wav")])])
+                .unwrap()
+                .event_id,
+            "file"
+        );
+        assert!(replayer
+            .take(0, &[json!({"mime_type": "text/csv"})])
+            .is_err());
+    }
+}
@@ -0,0 +1,400 @@
+use std::path::PathBuf;
+
+use crate::{
+    client::Client,
+    data::{
+        check_files, preprocess_data_with_cache, ChatContent, ChatFile, ChatMessage,
+        PredictionInput, PredictionOutput,
+    },
+    session::Session,
+    stream::PredictionStream,
+    structs::{ApiData, QueueDataMessage, QueueDataMessageOutput},
+    Error, Result,
+};
+
+const DEFAULT_CHAT_ROUTE: &str = "/chat";
+
+/// A new turn of a conversation, with optional attached files
+#[derive(Clone, Debug, Default)]
+pub struct ChatInput {
+    pub text: String,
+    pub files: Vec<PathBuf>,
+}
+
+impl ChatInput {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            files: vec![],
+        }
+    }
+
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(path.into());
+        self
+    }
+}
+
+impl From<&str> for ChatInput {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for ChatInput {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+/// A conversation with a `gr.ChatInterface` app.
+///
+/// The history is kept in the messages format. Apps that keep it in server-side
+/// state get it through the pinned [`Session`], apps with a `history` parameter
+/// get it sent along with every turn.
+///
+/// # Example
+///
+/// ```no_run
+/// use gradio::{ChatSession, Client, ClientOptions, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let client = Client::new("gradio/chatinterface_streaming_echo", ClientOptions::default()).await?;
+///     let mut chat = ChatSession::new(&client);
+///
+///     let mut reply = chat.send_streaming("Hello!").await?;
+///     while let Some(delta) = reply.next().await {
+///         print!("{}", delta?);
+///     }
+///     println!();
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ChatSession {
+    session: Session,
+    route: String,
+    history: Vec<ChatMessage>,
+    additional_inputs: Vec<PredictionInput>,
+}
+
+impl ChatSession {
+    /// Start a conversation on the `/chat` endpoint, must be called within a Tokio runtime
+    pub fn new(client: &Client) -> Self {
+        Self {
+            session: Session::new(client),
+            route: DEFAULT_CHAT_ROUTE.to_string(),
+            history: vec![],
+            additional_inputs: vec![],
+        }
+    }
+
+    pub fn with_route(mut self, route: &str) -> Self {
+        self.route = format!("/{}", route.trim_start_matches('/'));
+        self
+    }
+
+    /// Values for the `additional_inputs` of the chat interface, in order
+    pub fn with_additional_inputs(mut self, inputs: Vec<PredictionInput>) -> Self {
+        self.additional_inputs = inputs;
+        self
+    }
+
+    /// Continue an earlier conversation, for apps that take the history as a parameter
+    pub fn with_history(mut self, history: Vec<ChatMessage>) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn history(&self) -> &[ChatMessage] {
+        &self.history
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Forget the conversation, starting a new server-side session
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.session = Session::new(self.session.client());
+    }
+
+    /// Send a turn and wait for the complete reply
+    pub async fn send(&mut self, message: impl Into<ChatInput>) -> Result<Vec<ChatMessage>> {
+        self.send_streaming(message).await?.finish().await
+    }
+
+    /// Send a turn and stream the text of the reply as it is generated
+    pub async fn send_streaming(
+        &mut self,
+        message: impl Into<ChatInput>,
+    ) -> Result<ChatReplyStream<'_>> {
+        let message = message.into();
+        let client = self.session.client();
+        let parameters = client
+            .view_api()
+            .named_endpoints
+            .remove(&self.route)
+            .map(|endpoint| endpoint.parameters)
+            .unwrap_or_default();
+        let multimodal = parameters.first().is_some_and(|parameter| {
+            parameter
+                .component
+                .eq_ignore_ascii_case("multimodaltextbox")
+        });
+        if !message.files.is_empty() && !multimodal {
+            return Err(Error::ChatFilesNotSupported {
+                route: self.route.clone(),
+            });
+        }
+        if !message.files.is_empty() {
+            let constraints = client.file_constraints(&self.route)?;
+            let files: Vec<PredictionInput> = message
+                .files
+                .iter()
+                .cloned()
+                .map(PredictionInput::File)
+                .collect();
+            check_files(&[PredictionInput::Array(files)], &constraints).await?;
+        }
+
+        let files = preprocess_data_with_cache(
+            &client.http_client,
+            &client.api_root,
+            message
+                .files
+                .into_iter()
+                .map(PredictionInput::File)
+                .collect(),
+            client.uploads(),
+        )
+        .await?;
+
+        let mut turn = vec![];
+        for file in &files {
+            turn.push(ChatMessage::user(ChatContent::File(ChatFile {
+                file: serde_json::from_value(file.clone())?,
+                alt_text: None,
+            })));
+        }
+        if !message.text.is_empty() {
+            turn.push(ChatMessage::user(message.text.as_str()));
+        }
+
+        let message = if multimodal {
+            serde_json::json!({"text": message.text, "files": files})
+        } else {
+            serde_json::json!(message.text)
+        };
+        let data = self.inputs(&parameters, message)?;
+        let stream = client
+            .submit_without_state(&self.route, data, self.session.session_hash().to_string())
+            .await?;
+
+        Ok(ChatReplyStream {
+            chat: self,
+            stream,
+            turn,
+            text: String::new(),
+            reply: None,
+            finished: false,
+        })
+    }
+
+    /// Arrange the message, history and additional inputs by the endpoint parameters
+    fn inputs(
+        &self,
+        parameters: &[ApiData],
+        message: serde_json::Value,
+    ) -> Result<Vec<PredictionInput>> {
+        if parameters.is_empty() {
+            let mut data = vec![PredictionInput::Value(message)];
+            data.extend(self.additional_inputs.iter().cloned());
+            return Ok(data);
+        }
+
+        let mut additional = self.additional_inputs.iter().cloned();
+        let mut data = vec![PredictionInput::Value(message)];
+        for parameter in &parameters[1..] {
+            if parameter.parameter_name.as_deref() == Some("history") {
+                data.push(PredictionInput::Value(serde_json::to_value(&self.history)?));
+                continue;
+            }
+            let value = additional.next().unwrap_or_else(|| {
+                PredictionInput::Value(
+                    parameter
+                        .parameter_default
+                        .clone()
+                        .unwrap_or(serde_json::Value::Null),
+                )
+            });
+            data.push(value);
+        }
+        Ok(data)
+    }
+}
+
+/// The reply to a turn of a [`ChatSession`], being generated
+pub struct ChatReplyStream<'a> {
+    chat: &'a mut ChatSession,
+    stream: PredictionStream,
+    /// The user messages of this turn, added to the history once the reply completes
+    turn: Vec<ChatMessage>,
+    text: String,
+    reply: Option<Vec<ChatMessage>>,
+    finished: bool,
+}
+
+impl ChatReplyStream<'_> {
+    /// The underlying prediction, to follow its progress or logs
+    pub fn prediction(&mut self) -> &mut PredictionStream {
+        &mut self.stream
+    }
+
+    /// The text of the reply generated so far
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the next piece of the reply text, `None` once the reply is complete
+    pub async fn next(&mut self) -> Option<Result<String>> {
+        while !self.finished {
+            let message = match self.stream.next().await {
+                Some(Ok(message)) => message,
+                Some(Err(err)) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+                None => {
+                    self.finished = true;
+                    return Some(Err(Error::StreamEndedUnexpectedly));
+                }
+            };
+
+            match message {
+                QueueDataMessage::ProcessGenerating { output, .. }
+                | QueueDataMessage::ProcessStreaming { output, .. } => {
+                    let QueueDataMessageOutput::Success { data, .. } = output else {
+                        continue;
+                    };
+                    let reply = reply_messages(data.into_iter().next().unwrap_or_default());
+                    if let Some(delta) = self.advance(&reply) {
+                        return Some(Ok(delta));
+                    }
+                }
+                QueueDataMessage::ProcessCompleted { output, .. } => {
+                    self.finished = true;
+                    let outputs: Vec<PredictionOutput> = match output.try_into() {
+                        Ok(outputs) => outputs,
+                        Err(err) => return Some(Err(err)),
+                    };
+                    let value = match outputs.into_iter().next() {
+                        Some(PredictionOutput::Value(value)) => value,
+                        Some(PredictionOutput::File(file)) => {
+                            serde_json::json!({"file": file, "alt_text": null})
+                        }
+                        None => serde_json::Value::Null,
+                    };
+                    let reply = reply_messages(value);
+                    let delta = self.advance(&reply);
+
+                    self.chat.history.append(&mut self.turn);
+                    self.chat.history.extend(reply.iter().cloned());
+                    self.reply = Some(reply);
+                    return delta.map(Ok);
+                }
+                QueueDataMessage::UnexpectedError { message, .. } => {
+                    self.finished = true;
+                    return Some(Err(Error::UnexpectedRemoteError {
+                        message: message.unwrap_or_else(|| "Unexpected error".to_string()),
+                    }));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Wait for the reply to complete, returning the assistant messages
+    pub async fn finish(mut self) -> Result<Vec<ChatMessage>> {
+        while let Some(delta) = self.next().await {
+            delta?;
+        }
+        self.reply.ok_or(Error::StreamEndedUnexpectedly)
+    }
+
+    /// Update the reply text, returns the newly generated part
+    fn advance(&mut self, reply: &[ChatMessage]) -> Option<String> {
+        let text = reply
+            .iter()
+            .filter_map(ChatMessage::text)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let delta = match text.strip_prefix(self.text.as_str()) {
+            Some(delta) => delta.to_string(),
+            // the app rewrote earlier text, send the whole reply again
+            None => text.clone(),
+        };
+        self.text = text;
+        (!delta.is_empty()).then_some(delta)
+    }
+}
+
+/// Parse the reply of a chat function, which may be text, a file, a message or
+/// a list of messages
+fn reply_messages(value: serde_json::Value) -> Vec<ChatMessage> {
+    match value {
+        serde_json::Value::Null => vec![],
+        serde_json::Value::String(text) => vec![ChatMessage::assistant(text)],
+        serde_json::Value::Array(items) => {
+            match serde_json::from_value::<Vec<ChatMessage>>(serde_json::Value::Array(
+                items.clone(),
+            )) {
+                Ok(messages) => messages,
+                Err(_) => vec![ChatMessage::assistant(chat_content(
+                    serde_json::Value::Array(items),
+                ))],
+            }
+        }
+        value => match serde_json::from_value::<ChatMessage>(value.clone()) {
+            Ok(message) => vec![message],
+            Err(_) => vec![ChatMessage::assistant(chat_content(value))],
+        },
+    }
+}
+
+fn chat_content(value: serde_json::Value) -> ChatContent {
+    serde_json::from_value(value.clone()).unwrap_or(ChatContent::Component(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::reply_messages;
+    use crate::data::ChatContent;
+
+    #[test]
+    fn parses_text_message_and_multimodal_replies() {
+        let text = reply_messages(json!("Hello"));
+        assert_eq!(text[0].role, "assistant");
+        assert_eq!(text[0].text().as_deref(), Some("Hello"));
+
+        let messages = reply_messages(json!([
+            {"role": "assistant", "content": "Thinking", "metadata": {"title": "Tool"}},
+            {"role": "assistant", "content": "Done"}
+        ]));
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].metadata.is_some());
+
+        let parts = reply_messages(json!([
+            {"type": "text", "text": "Here is the image"},
+            {"type": "file", "file": {"path": "a.png", "meta": {"_type": "gradio.FileData"}}}
+        ]));
+        assert_eq!(parts.len(), 1);
+        assert!(matches!(parts[0].content, ChatContent::Parts(_)));
+        assert_eq!(parts[0].text().as_deref(), Some("Here is the image"));
+    }
+}
@@ -1,35 +1,162 @@
-use rand::{distributions::Alphanumeric, Rng};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use futures_util::stream::{self, StreamExt, TryStreamExt};
 use regex::Regex;
+use tokio::sync::{broadcast, OnceCell};
 
 use crate::constants::*;
-use crate::preprocess_data;
 use crate::structs::*;
 use crate::{
-    data::{PredictionInput, PredictionOutput},
+    cache::{CacheOptions, ResultCache},
+    cassette::{Cassette, RecordedUpload, Recorder, Replayer, Tape},
+    data::{ComponentValue, PredictionInput, PredictionOutput},
+    endpoint::{Endpoint, FromPredictionOutputs},
+    report::PredictionReport,
+    session::{generate_session_hash, Session},
+    snapshot::ClientSnapshot,
     space::wake_up_space,
-    stream::PredictionStream,
+    stream::{PredictionStream, ReconnectPolicy},
+    trace::{info_span, warn, Instrument},
     Error, Result,
 };
+use crate::{check_files, preprocess_data_with_cache, FileConstraints, UploadCache};
 
-#[derive(Default)]
+/// Options for [`Client::new`].
+///
+/// New options are added over time, so build these from a constructor or
+/// [`ClientOptions::default`] rather than a struct literal.
+#[derive(Clone, Default)]
+#[non_exhaustive]
 pub struct ClientOptions {
     pub hf_token: Option<String>,
     pub auth: Option<(String, String)>,
+    pub reconnect: ReconnectPolicy,
+    /// Opt-in on-disk cache for the results of [`Client::predict`]
+    pub cache: Option<CacheOptions>,
+    /// Capture the traffic of the client into a [`Cassette`], see [`Client::cassette`]
+    pub record: bool,
 }
 
 impl ClientOptions {
     pub fn with_hf_token(hf_token: String) -> ClientOptions {
         Self {
             hf_token: Some(hf_token),
-            auth: None,
+            ..Default::default()
         }
     }
 
     pub fn with_auth(username: String, password: String) -> Self {
         Self {
-            hf_token: None,
             auth: Some((username, password)),
+            ..Default::default()
+        }
+    }
+
+    /// Set how prediction streams reconnect to the queue after a network error
+    pub fn with_reconnect(mut self, reconnect: ReconnectPolicy) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+}
+
+/// Per-submission options
+#[derive(Clone, Debug, Default)]
+pub struct SubmitOptions {
+    /// Maximum time to wait in the queue before processing starts
+    pub queue_timeout: Option<Duration>,
+    /// Maximum time the app may spend processing the prediction
+    pub processing_timeout: Option<Duration>,
+}
+
+impl SubmitOptions {
+    pub fn with_timeouts(
+        queue_timeout: Option<Duration>,
+        processing_timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            queue_timeout,
+            processing_timeout,
+        }
+    }
+}
+
+/// A change to the app observed while a prediction was running
+#[derive(Clone, Debug)]
+pub enum AppEvent {
+    /// A `gr.render` block re-rendered, and the cached components and
+    /// dependencies (and therefore the routes available) were updated
+    Rendered { render_id: Option<i64> },
+    /// The server reported that these state components changed
+    StateChanged { ids: Vec<serde_json::Value> },
+}
+
+#[derive(Debug)]
+pub(crate) struct AppState {
+    config: RwLock<AppConfig>,
+    api_info: RwLock<ApiInfo>,
+    events: broadcast::Sender<AppEvent>,
+}
+
+impl AppState {
+    fn new(config: AppConfig, api_info: ApiInfo) -> Self {
+        let (events, _) = broadcast::channel(16);
+        Self {
+            config: RwLock::new(config),
+            api_info: RwLock::new(api_info),
+            events,
+        }
+    }
+
+    pub(crate) fn config(&self) -> AppConfig {
+        self.config
+            .read()
+            .expect("app config lock poisoned")
+            .clone()
+    }
+
+    fn api_info(&self) -> ApiInfo {
+        self.api_info
+            .read()
+            .expect("api info lock poisoned")
+            .clone()
+    }
+
+    fn replace(&self, config: AppConfig, api_info: ApiInfo) {
+        *self.config.write().expect("app config lock poisoned") = config;
+        *self.api_info.write().expect("api info lock poisoned") = api_info;
+    }
+
+    pub(crate) fn apply_output(&self, output: &QueueDataMessageOutput) -> Result<()> {
+        let QueueDataMessageOutput::Success {
+            render_config,
+            changed_state_ids,
+            ..
+        } = output
+        else {
+            return Ok(());
+        };
+
+        if let Some(render_config) = render_config {
+            let render: RenderConfig = serde_json::from_value(render_config.clone())?;
+            self.config
+                .write()
+                .expect("app config lock poisoned")
+                .apply_render_config(&render);
+            let _ = self.events.send(AppEvent::Rendered {
+                render_id: render.render_id,
+            });
         }
+
+        if let Some(ids) = changed_state_ids {
+            if !ids.is_empty() {
+                let _ = self
+                    .events
+                    .send(AppEvent::StateChanged { ids: ids.clone() });
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -40,8 +167,14 @@ pub struct Client {
     pub http_client: reqwest::Client,
     pub api_root: String,
     pub space_id: Option<String>,
-    config: AppConfig,
-    api_info: ApiInfo,
+    state: Arc<AppState>,
+    reconnect: ReconnectPolicy,
+    app_reference: String,
+    cache: Option<Arc<ResultCache>>,
+    uploads: Arc<UploadCache>,
+    tape: Option<Arc<Tape>>,
+    /// Checks the app version against the server once, before the first submission
+    revalidation: Option<Arc<OnceCell<()>>>,
 }
 
 impl Client {
@@ -76,31 +209,35 @@ impl Client {
     /// }
     /// ```
     pub async fn new(app_reference: &str, options: ClientOptions) -> Result<Self> {
-        let session_hash: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(10)
-            .map(char::from)
-            .collect();
+        let session_hash = generate_session_hash();
 
         let http_client = Client::build_http_client(&options.hf_token)?;
 
-        let (mut api_root, space_id) =
-            Client::resolve_app_reference(&http_client, app_reference).await?;
+        let (api_root, space_id, config, api_info) = async {
+            let (mut api_root, space_id) =
+                Client::resolve_app_reference(&http_client, app_reference).await?;
 
-        if let Some((username, password)) = &options.auth {
-            Client::authenticate(&http_client, &api_root, username, password).await?;
-        }
+            if let Some((username, password)) = &options.auth {
+                Client::authenticate(&http_client, &api_root, username, password).await?;
+            }
 
-        if let Some(space_id) = &space_id {
-            wake_up_space(&http_client, space_id).await?;
-        }
+            if let Some(space_id) = &space_id {
+                wake_up_space(&http_client, space_id).await?;
+            }
 
-        let config = Client::fetch_config(&http_client, &api_root).await?;
-        if let Some(ref api_prefix) = config.api_prefix {
-            api_root = Client::join_url_path(&api_root, api_prefix);
-        }
+            let config = Client::fetch_config(&http_client, &api_root).await?;
+            if let Some(ref api_prefix) = config.api_prefix {
+                api_root = Client::join_url_path(&api_root, api_prefix);
+            }
 
-        let api_info = Client::fetch_api_info(&http_client, &api_root).await?;
+            let api_info = Client::fetch_api_info(&http_client, &api_root).await?;
+            Ok::<_, Error>((api_root, space_id, config, api_info))
+        }
+        .instrument(info_span!("resolve_app", app_reference = %app_reference))
+        .await?;
+        let tape = options
+            .record
+            .then(|| Arc::new(Tape::Recording(Recorder::new(config.clone()))));
 
         Ok(Self {
             session_hash,
@@ -108,17 +245,181 @@ impl Client {
             http_client,
             api_root,
             space_id,
-            config,
-            api_info,
+            state: Arc::new(AppState::new(config, api_info)),
+            reconnect: options.reconnect,
+            app_reference: app_reference.to_string(),
+            cache: options.cache.map(|cache| Arc::new(ResultCache::new(cache))),
+            uploads: Arc::new(UploadCache::new()),
+            tape,
+            revalidation: None,
+        })
+    }
+
+    /// Create a client from a saved [`ClientSnapshot`] without resolving the app
+    /// on the Hub, waking it up or fetching its config and API info.
+    ///
+    /// With `revalidate`, the app version is checked against the server before the
+    /// first submission, and the config and API info are refetched if it changed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use gradio::{Client, ClientOptions, ClientSnapshot, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let path = "hello_world.json";
+    ///     let client = match ClientSnapshot::load(path).await {
+    ///         Ok(snapshot) => Client::from_snapshot(snapshot, ClientOptions::default(), true).await?,
+    ///         Err(_) => {
+    ///             let client = Client::new("gradio/hello_world", ClientOptions::default()).await?;
+    ///             client.snapshot().save(path).await?;
+    ///             client
+    ///         }
+    ///     };
+    ///     println!("{:?}", client.view_api());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn from_snapshot(
+        snapshot: ClientSnapshot,
+        options: ClientOptions,
+        revalidate: bool,
+    ) -> Result<Self> {
+        let http_client = Client::build_http_client(&options.hf_token)?;
+
+        if let Some((username, password)) = &options.auth {
+            let root = Client::config_root(&snapshot.api_root, &snapshot.config);
+            Client::authenticate(&http_client, root, username, password).await?;
+        }
+
+        let tape = options
+            .record
+            .then(|| Arc::new(Tape::Recording(Recorder::new(snapshot.config.clone()))));
+
+        Ok(Self {
+            session_hash: generate_session_hash(),
+            jwt: None,
+            http_client,
+            api_root: snapshot.api_root,
+            space_id: snapshot.space_id,
+            state: Arc::new(AppState::new(snapshot.config, snapshot.api_info)),
+            reconnect: options.reconnect,
+            app_reference: snapshot.app_reference,
+            cache: options.cache.map(|cache| Arc::new(ResultCache::new(cache))),
+            uploads: Arc::new(UploadCache::new()),
+            tape,
+            revalidation: revalidate.then(|| Arc::new(OnceCell::new())),
+        })
+    }
+
+    /// Save the resolved app to restore it later with [`Client::from_snapshot`]
+    pub fn snapshot(&self) -> ClientSnapshot {
+        ClientSnapshot {
+            app_reference: self.app_reference.clone(),
+            api_root: self.api_root.clone(),
+            space_id: self.space_id.clone(),
+            config: self.state.config(),
+            api_info: self.state.api_info(),
+        }
+    }
+
+    /// Refetch the config and API info if the app version changed since the snapshot
+    async fn revalidate(&self) -> Result<()> {
+        let Some(revalidation) = &self.revalidation else {
+            return Ok(());
+        };
+
+        revalidation
+            .get_or_try_init(|| async {
+                let current = self.state.config();
+                let root = Client::config_root(&self.api_root, &current);
+                let config = Client::fetch_config(&self.http_client, root).await?;
+                if config.version != current.version {
+                    let api_info =
+                        Client::fetch_api_info(&self.http_client, &self.api_root).await?;
+                    self.state.replace(config, api_info);
+                }
+                Ok::<(), Error>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Create a client that replays a recorded [`Cassette`] without network access.
+    ///
+    /// Predictions must be made with the same routes and inputs as when recording,
+    /// otherwise [`Error::CassetteMismatch`] is returned. File outputs still point
+    /// to the recorded server and are not available offline.
+    pub fn from_cassette(cassette: Cassette) -> Result<Self> {
+        let uploads = cassette
+            .uploads
+            .into_iter()
+            .map(|upload| ((cassette.api_root.clone(), upload.sha256), upload.path));
+
+        Ok(Self {
+            session_hash: generate_session_hash(),
+            jwt: None,
+            http_client: Client::build_http_client(&None)?,
+            api_root: cassette.api_root.clone(),
+            space_id: cassette.space_id,
+            state: Arc::new(AppState::new(cassette.config, cassette.api_info)),
+            reconnect: ReconnectPolicy::disabled(),
+            app_reference: cassette.api_root.clone(),
+            cache: None,
+            uploads: Arc::new(UploadCache::offline(uploads)),
+            tape: Some(Arc::new(Tape::Replaying(Replayer::new(
+                cassette.predictions,
+            )))),
+            revalidation: None,
+        })
+    }
+
+    /// The traffic recorded so far, if the client was created with
+    /// [`ClientOptions::record`]
+    pub fn cassette(&self) -> Option<Cassette> {
+        let Some(Tape::Recording(recorder)) = self.tape.as_deref() else {
+            return None;
+        };
+
+        Some(Cassette {
+            api_root: self.api_root.clone(),
+            space_id: self.space_id.clone(),
+            config: recorder.config().clone(),
+            api_info: self.state.api_info(),
+            uploads: self
+                .uploads
+                .entries(&self.api_root)
+                .into_iter()
+                .map(|(sha256, path)| RecordedUpload { sha256, path })
+                .collect(),
+            predictions: recorder.predictions(),
         })
     }
 
+    /// Whether the client replays a cassette instead of talking to a server
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.tape.as_deref(), Some(Tape::Replaying(_)))
+    }
+
     pub fn view_config(&self) -> AppConfig {
-        self.config.clone()
+        self.state.config()
     }
 
     pub fn view_api(&self) -> ApiInfo {
-        self.api_info.clone()
+        self.state.api_info()
+    }
+
+    /// Subscribe to changes of the app made during predictions, such as
+    /// `gr.render` blocks adding or removing routes
+    pub fn subscribe_events(&self) -> broadcast::Receiver<AppEvent> {
+        self.state.events.subscribe()
+    }
+
+    /// Create a [`Session`] whose submissions share one `session_hash`,
+    /// keeping the app's heartbeat alive while it exists
+    pub fn session(&self) -> Session {
+        Session::new(self)
     }
 
     pub async fn submit(
@@ -126,24 +427,358 @@ impl Client {
         route: &str,
         data: Vec<PredictionInput>,
     ) -> Result<PredictionStream> {
-        let data = preprocess_data(&self.http_client, &self.api_root, data).await?;
-        let fn_index = Client::resolve_fn_index(&self.config, route)?;
-        PredictionStream::new(
-            &self.http_client,
-            &self.api_root,
-            &self.config.protocol,
-            fn_index,
-            data,
-        )
-        .await
+        self.submit_with_options(route, data, SubmitOptions::default())
+            .await
+    }
+
+    pub async fn submit_with_options(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+        options: SubmitOptions,
+    ) -> Result<PredictionStream> {
+        self.submit_in_session(route, data, generate_session_hash(), &options)
+            .await
     }
 
+    /// Run a prediction and wait for its outputs.
+    ///
+    /// If a result cache is configured, outputs are served from and stored in it.
     pub async fn predict(
         &self,
         route: &str,
         data: Vec<PredictionInput>,
     ) -> Result<Vec<PredictionOutput>> {
+        let Some(cache) = &self.cache else {
+            let mut stream = self.submit(route, data).await?;
+            return Client::wait_for_outputs(&mut stream).await;
+        };
+
+        self.revalidate().await?;
+        let version = self.state.config().version;
+        let key = ResultCache::key(&self.app_reference, route, &version, &data).await?;
+        if let Some(outputs) = cache.get(&key).await? {
+            return Ok(outputs);
+        }
+
         let mut stream = self.submit(route, data).await?;
+        let outputs = Client::wait_for_outputs(&mut stream).await?;
+        match cache.put(&key, &outputs, &self.http_client).await {
+            Ok(stored) => Ok(stored),
+            Err(err) => {
+                warn!("Could not cache prediction result: {}", err);
+                Ok(outputs)
+            }
+        }
+    }
+
+    /// Call a typed endpoint, see [`Endpoint`]
+    pub async fn call<E: Endpoint>(&self, endpoint: E) -> Result<E::Output> {
+        let outputs = self.predict(E::ROUTE, endpoint.into_inputs()).await?;
+        E::Output::from_prediction_outputs(outputs)
+    }
+
+    /// Check the files in `data` against the `max_file_size` of the app and the
+    /// `file_types` of the parameters of `route`, without uploading them
+    pub async fn check_files(&self, route: &str, data: &[PredictionInput]) -> Result<()> {
+        self.revalidate().await?;
+        check_files(data, &self.file_constraints(route)?).await
+    }
+
+    /// The constraints on the files of each parameter of `route`
+    pub(crate) fn file_constraints(&self, route: &str) -> Result<Vec<FileConstraints>> {
+        let config = self.state.config();
+        let (_, dependency) = Client::resolve_dependency(&config, route)?;
+        let max_file_size = config
+            .max_file_size
+            .and_then(|size| u64::try_from(size).ok());
+        let mut file_types = config.accepted_file_types(&dependency.inputs);
+        // the size limit applies even where the components are unknown
+        let parameters = self
+            .state
+            .api_info()
+            .named_endpoints
+            .get(&format!("/{}", route.trim_start_matches('/')))
+            .map_or(0, |endpoint| endpoint.parameters.len());
+        file_types.resize(file_types.len().max(parameters), None);
+        Ok(file_types
+            .into_iter()
+            .map(|file_types| FileConstraints {
+                max_file_size,
+                file_types,
+            })
+            .collect())
+    }
+
+    /// Files uploaded by this client, reused when the same content is sent again
+    pub fn uploads(&self) -> &UploadCache {
+        &self.uploads
+    }
+
+    /// The result cache, if one was configured
+    pub fn cache(&self) -> Option<&ResultCache> {
+        self.cache.as_deref()
+    }
+
+    /// Like [`Client::predict`], but bounded by the deadlines in `options`.
+    ///
+    /// Returns [`Error::Timeout`] naming the phase that ran out of time.
+    pub async fn predict_with_options(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+        options: SubmitOptions,
+    ) -> Result<Vec<PredictionOutput>> {
+        let mut stream = self.submit_with_options(route, data, options).await?;
+        Client::wait_for_outputs(&mut stream).await
+    }
+
+    /// Run predictions for many inputs against one route, with at most
+    /// `concurrency` of them submitted at a time.
+    ///
+    /// Results are returned in the order of `inputs`, and a failed item does not
+    /// abort the rest of the batch.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use gradio::{Client, ClientOptions, PredictionInput, Result};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let client = Client::new("gradio/hello_world", ClientOptions::default()).await?;
+    ///     let names = ["Alice", "Bob", "Carol"];
+    ///     let results = client
+    ///         .predict_many(
+    ///             "/predict",
+    ///             names.iter().map(|name| vec![PredictionInput::from_value(name)]),
+    ///             2,
+    ///         )
+    ///         .await;
+    ///     for result in results {
+    ///         println!("{:?}", result);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn predict_many<I>(
+        &self,
+        route: &str,
+        inputs: I,
+        concurrency: usize,
+    ) -> Vec<Result<Vec<PredictionOutput>>>
+    where
+        I: IntoIterator<Item = Vec<PredictionInput>>,
+    {
+        stream::iter(inputs)
+            .map(|data| self.predict(route, data))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// The maximum batch size of a route declared with `batch=True`, or `None`
+    /// if the route takes single inputs
+    pub fn batch_size(&self, route: &str) -> Result<Option<usize>> {
+        let (_, dependency) = Client::resolve_dependency(&self.state.config(), route)?;
+        Ok(dependency.batch_size())
+    }
+
+    /// Run many single inputs through a `batch=True` route.
+    ///
+    /// Inputs are submitted as separate predictions, up to `max_batch_size` at a
+    /// time, and the server groups them into batches. Results are returned in the
+    /// order of `inputs`, failing on the first failed prediction.
+    pub async fn predict_batch(
+        &self,
+        route: &str,
+        inputs: Vec<Vec<PredictionInput>>,
+    ) -> Result<Vec<Vec<PredictionOutput>>> {
+        self.revalidate().await?;
+        let batch_size = self
+            .batch_size(route)?
+            .ok_or_else(|| Error::EndpointNotBatched {
+                route: route.to_string(),
+            })?;
+
+        stream::iter(inputs)
+            .map(|data| self.predict(route, data))
+            .buffered(batch_size)
+            .try_collect()
+            .await
+    }
+
+    /// Like [`Client::predict`], but converts the outputs of known components
+    /// such as `gr.Chatbot` or `gr.Dataframe` into typed values
+    pub async fn predict_components(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+    ) -> Result<Vec<ComponentValue>> {
+        let outputs = self.predict(route, data).await?;
+        let route = format!("/{}", route.trim_start_matches('/'));
+        let returns = self
+            .view_api()
+            .named_endpoints
+            .remove(&route)
+            .map(|endpoint| endpoint.returns)
+            .unwrap_or_default();
+        ComponentValue::from_outputs(outputs, &returns)
+    }
+
+    /// Like [`Client::predict`], but also returns when each step of the prediction
+    /// happened, bypassing the result cache
+    pub async fn predict_with_report(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+    ) -> Result<(Vec<PredictionOutput>, PredictionReport)> {
+        let mut stream = self.submit(route, data).await?;
+        let outputs = Client::wait_for_outputs(&mut stream).await?;
+        Ok((outputs, stream.report().clone()))
+    }
+
+    /// Like [`Client::predict`], but also returns the messages the app logged
+    /// with `gr.Info`, `gr.Warning` or `print` while processing
+    pub async fn predict_with_logs(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+    ) -> Result<(Vec<PredictionOutput>, Vec<LogMessage>)> {
+        let mut stream = self.submit(route, data).await?;
+        let mut receiver = stream.subscribe_logs();
+        let outputs = Client::wait_for_outputs(&mut stream).await?;
+
+        let mut logs = vec![];
+        while let Ok(log) = receiver.try_recv() {
+            logs.push(log);
+        }
+        Ok((outputs, logs))
+    }
+
+    pub(crate) async fn submit_in_session(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+        session_hash: String,
+        options: &SubmitOptions,
+    ) -> Result<PredictionStream> {
+        self.submit_prepared(route, data, session_hash, options, false)
+            .await
+    }
+
+    /// Like [`Client::submit_in_session`], but `data` follows the parameters of the
+    /// API info, so `gr.State` inputs get placeholders and state outputs are
+    /// dropped, as [`ChatSession`](crate::ChatSession) expects
+    pub(crate) async fn submit_without_state(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+        session_hash: String,
+    ) -> Result<PredictionStream> {
+        self.submit_prepared(route, data, session_hash, &SubmitOptions::default(), true)
+            .await
+    }
+
+    async fn submit_prepared(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+        session_hash: String,
+        options: &SubmitOptions,
+        without_state: bool,
+    ) -> Result<PredictionStream> {
+        self.check_files(route, &data).await?;
+        let mut report = PredictionReport::new();
+        let data =
+            preprocess_data_with_cache(&self.http_client, &self.api_root, data, &self.uploads)
+                .await?;
+        report.uploaded_at = Some(Instant::now());
+        self.join_queue(route, data, session_hash, options, without_state, report)
+            .await
+    }
+
+    /// Join the queue with preprocessed data
+    async fn join_queue(
+        &self,
+        route: &str,
+        data: Vec<serde_json::Value>,
+        session_hash: String,
+        options: &SubmitOptions,
+        without_state: bool,
+        mut report: PredictionReport,
+    ) -> Result<PredictionStream> {
+        self.revalidate().await?;
+        let config = self.state.config();
+        let (fn_index, dependency) = Client::resolve_dependency(&config, route)?;
+        let data = if without_state {
+            insert_state_placeholders(
+                data,
+                &config.state_positions(&dependency.inputs),
+                dependency.inputs.len(),
+            )
+        } else {
+            data
+        };
+        let span = info_span!(
+            "prediction",
+            route = %route,
+            fn_index,
+            event_id = tracing::field::Empty
+        );
+        let join_span = info_span!(parent: &span, "queue_join");
+        let mut stream = match self.tape.as_deref() {
+            Some(Tape::Replaying(replayer)) => PredictionStream::replay(
+                &self.http_client,
+                &self.api_root,
+                &config.protocol,
+                replayer.take(fn_index, &data)?,
+                session_hash,
+            )?,
+            Some(Tape::Recording(recorder)) => {
+                let mut stream = PredictionStream::join(
+                    &self.http_client,
+                    &self.api_root,
+                    &config.protocol,
+                    fn_index,
+                    data.clone(),
+                    session_hash,
+                )
+                .instrument(join_span)
+                .await?;
+                stream.set_frame_log(recorder.start(fn_index, data, &stream.event_id));
+                stream
+            }
+            None => {
+                PredictionStream::join(
+                    &self.http_client,
+                    &self.api_root,
+                    &config.protocol,
+                    fn_index,
+                    data,
+                    session_hash,
+                )
+                .instrument(join_span)
+                .await?
+            }
+        };
+        report.route = Some(route.to_string());
+        report.queue_joined_at = Some(Instant::now());
+        stream.set_report(report);
+        span.record("event_id", stream.event_id.as_str());
+        stream.set_span(span);
+        stream.set_app_state(self.state.clone());
+        stream.set_timeouts(options.queue_timeout, options.processing_timeout);
+        stream.set_reconnect_policy(self.reconnect.clone());
+        if without_state {
+            stream.set_skipped_outputs(config.state_positions(&dependency.outputs));
+        }
+        Ok(stream)
+    }
+
+    pub(crate) async fn wait_for_outputs(
+        stream: &mut PredictionStream,
+    ) -> Result<Vec<PredictionOutput>> {
         while let Some(message) = stream.next().await {
             match message {
                 Ok(message) => match message {
@@ -165,7 +800,7 @@ impl Client {
                         });
                     }
                     QueueDataMessage::Unknown(m) => {
-                        eprintln!("[warning] Skipping unknown message: {:?}", m);
+                        warn!("Skipping unknown message: {:?}", m);
                     }
                 },
                 Err(err) => {
@@ -248,8 +883,8 @@ impl Client {
         let config: AppConfigVersionOnly = serde_json::from_value(json.clone())?;
 
         if !Client::supports_version(&config.version) {
-            eprintln!(
-                "Warning: This client is supposed to work with Gradio 4, 5, and 6. The current version of the app is {}, which may cause issues.",
+            warn!(
+                "This client is supposed to work with Gradio 4, 5, and 6. The current version of the app is {}, which may cause issues.",
                 config.version
             );
         }
@@ -268,7 +903,7 @@ impl Client {
         res.json::<ApiInfo>().await.map_err(Error::from)
     }
 
-    fn resolve_fn_index(config: &AppConfig, route: &str) -> Result<i64> {
+    fn resolve_dependency(config: &AppConfig, route: &str) -> Result<(i64, Dependency)> {
         let route = route.trim_start_matches('/');
         let found = config
             .dependencies
@@ -280,12 +915,24 @@ impl Client {
             })?;
 
         if found.1.id == -1 {
-            Ok(found.0 as i64)
+            Ok((found.0 as i64, found.1.clone()))
         } else {
-            Ok(found.1.id)
+            Ok((found.1.id, found.1.clone()))
         }
     }
 
+    /// The root the config is served from, which is the API root without the API prefix
+    fn config_root<'a>(api_root: &'a str, config: &AppConfig) -> &'a str {
+        let prefix = config.api_prefix.as_deref().unwrap_or("").trim_matches('/');
+        if prefix.is_empty() {
+            return api_root;
+        }
+        api_root
+            .strip_suffix(prefix)
+            .and_then(|root| root.strip_suffix('/'))
+            .unwrap_or(api_root)
+    }
+
     fn join_url_path(base: &str, suffix: &str) -> String {
         let base = base.trim_end_matches('/');
         let suffix = suffix.trim_matches('/');
@@ -306,9 +953,28 @@ impl Client {
     }
 }
 
+/// Insert `null` for the state inputs the server fills in, unless the caller
+/// already passed a value for every input
+fn insert_state_placeholders(
+    mut data: Vec<serde_json::Value>,
+    positions: &[usize],
+    inputs: usize,
+) -> Vec<serde_json::Value> {
+    if positions.is_empty() || data.len() + positions.len() != inputs {
+        return data;
+    }
+    for &position in positions {
+        data.insert(position, serde_json::Value::Null);
+    }
+    data
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Client;
+    use serde_json::json;
+
+    use super::{insert_state_placeholders, Client, ClientOptions};
+    use crate::snapshot::ClientSnapshot;
 
     #[test]
     fn join_url_path_normalizes_slashes() {
@@ -322,6 +988,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn config_root_strips_api_prefix() {
+        let config = |api_prefix: Option<&str>| {
+            serde_json::from_value(json!({
+                "components": [], "dependencies": [], "mode": "blocks", "root": "",
+                "theme": "default", "title": "Gradio", "version": "5.0.0",
+                "protocol": "sse_v3", "layout": {}, "stylesheets": [],
+                "api_prefix": api_prefix
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            Client::config_root(
+                "https://example.com/gradio_api",
+                &config(Some("/gradio_api/"))
+            ),
+            "https://example.com"
+        );
+        assert_eq!(
+            Client::config_root("https://example.com", &config(None)),
+            "https://example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn restores_client_from_snapshot() {
+        let snapshot: ClientSnapshot = serde_json::from_value(json!({
+            "app_reference": "gradio/hello_world",
+            "api_root": "https://gradio-hello-world.hf.space/gradio_api",
+            "space_id": "gradio/hello_world",
+            "config": {
+                "components": [], "dependencies": [{"api_name": "predict", "id": 0}],
+                "mode": "interface", "root": "", "theme": "default", "title": "Gradio",
+                "version": "5.9.1", "protocol": "sse_v3", "layout": {}, "stylesheets": [],
+                "api_prefix": "/gradio_api"
+            },
+            "api_info": {"named_endpoints": {}}
+        }))
+        .unwrap();
+
+        let client = Client::from_snapshot(snapshot, ClientOptions::default(), false)
+            .await
+            .unwrap();
+
+        assert_eq!(client.view_config().version, "5.9.1");
+        assert_eq!(client.batch_size("/predict").unwrap(), None);
+        assert_eq!(client.snapshot().app_reference, "gradio/hello_world");
+    }
+
     #[test]
     fn supports_gradio_4_5_and_6() {
         assert!(Client::supports_version("4.31.2"));
@@ -330,4 +1046,16 @@ mod tests {
         assert!(!Client::supports_version("7.0.0"));
         assert!(!Client::supports_version("invalid"));
     }
+
+    #[test]
+    fn inserts_placeholders_for_state_inputs() {
+        assert_eq!(
+            insert_state_placeholders(vec![json!("Hi"), json!(0.5)], &[1], 3),
+            vec![json!("Hi"), json!(null), json!(0.5)]
+        );
+        assert_eq!(
+            insert_state_placeholders(vec![json!("Hi"), json!([])], &[1], 2),
+            vec![json!("Hi"), json!([])]
+        );
+    }
 }
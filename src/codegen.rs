@@ -0,0 +1,680 @@
+//! Generate a typed Rust client from the API info of an app.
+//!
+//! The generated module has one async method per named endpoint. Required
+//! parameters are arguments, parameters with defaults go in an options struct
+//! whose `Default` matches the app, and outputs are returned as a struct.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::{
+    snapshot::SavedApi,
+    structs::{ApiData, ApiInfo},
+    Result,
+};
+
+const KEYWORDS: [&str; 52] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Methods of the generated client that endpoints must not shadow
+const RESERVED_METHODS: [&str; 3] = ["new", "from_client", "client"];
+/// Identifiers the generated methods use next to the fields
+const RESERVED_FIELDS: [&str; 4] = ["options", "output", "outputs", "data"];
+
+#[derive(Clone, Debug)]
+pub struct CodegenOptions {
+    /// The Space ID or URL the generated client connects to
+    pub app_reference: String,
+    /// Name of the generated client struct
+    pub struct_name: String,
+}
+
+impl CodegenOptions {
+    /// Options for `app_reference`, naming the client after its last path segment
+    pub fn new(app_reference: impl Into<String>) -> Self {
+        let app_reference = app_reference.into();
+        let name = app_reference
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        let struct_name = match pascal_case(name) {
+            name if name.is_empty() => "App".to_string(),
+            name => name,
+        };
+        Self {
+            app_reference,
+            struct_name,
+        }
+    }
+
+    pub fn with_struct_name(mut self, struct_name: impl Into<String>) -> Self {
+        self.struct_name = struct_name.into();
+        self
+    }
+}
+
+/// Generate the client module from a JSON file, for use in build scripts.
+///
+/// The file is read with [`SavedApi::load`].
+pub fn generate_from_file(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    options: &CodegenOptions,
+) -> Result<()> {
+    let saved = SavedApi::load(input)?;
+    std::fs::write(output, generate(&saved.api_info, options))?;
+    Ok(())
+}
+
+/// Generate the source of a client module for the named endpoints of `api_info`
+pub fn generate(api_info: &ApiInfo, options: &CodegenOptions) -> String {
+    let mut routes: Vec<&String> = api_info
+        .named_endpoints
+        .iter()
+        .filter(|(_, endpoint)| endpoint.show_api != Some(false))
+        .map(|(route, _)| route)
+        .collect();
+    routes.sort();
+
+    let client = &options.struct_name;
+    let mut methods = String::new();
+    let mut types = String::new();
+    let mut method_names: HashSet<String> = RESERVED_METHODS.map(String::from).into();
+    let mut has_outputs = false;
+    for route in routes {
+        let endpoint = &api_info.named_endpoints[route];
+        let method = unique(snake_case(route), &mut method_names);
+        let endpoint = Endpoint::new(route, method, &endpoint.parameters, &endpoint.returns);
+        endpoint.write_method(&mut methods);
+        endpoint.write_types(&mut types);
+        has_outputs |= !endpoint.outputs.is_empty();
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// Generated by `gr codegen` from the API info of {}, do not edit.\n",
+        options.app_reference
+    );
+    if has_outputs {
+        out.push_str(
+            "use gradio::{Client, ClientOptions, PredictionInput, PredictionOutput, Result};\n\n",
+        );
+    } else {
+        out.push_str("use gradio::{Client, ClientOptions, PredictionInput, Result};\n\n");
+    }
+    let _ = writeln!(out, "/// Typed client for {}", options.app_reference);
+    let _ = writeln!(out, "#[derive(Clone, Debug)]\npub struct {} {{", client);
+    out.push_str("    client: Client,\n}\n\n");
+    let _ = writeln!(out, "impl {} {{", client);
+    let _ = writeln!(
+        out,
+        "    pub const APP_REFERENCE: &'static str = {:?};\n",
+        options.app_reference
+    );
+    out.push_str(
+        "    pub async fn new(options: ClientOptions) -> Result<Self> {
+        let client = Client::new(Self::APP_REFERENCE, options).await?;
+        Ok(Self::from_client(client))
+    }
+
+    pub fn from_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+",
+    );
+    out.push_str(&methods);
+    out.push_str("}\n");
+    out.push_str(&types);
+    if !has_outputs {
+        return out;
+    }
+    out.push_str(
+        "
+fn output<T: gradio::serde::de::DeserializeOwned>(
+    outputs: &[PredictionOutput],
+    index: usize,
+) -> Result<T> {
+    outputs
+        .get(index)
+        .cloned()
+        .ok_or(gradio::Error::MissingOutput { index })?
+        .decode()
+}
+",
+    );
+    out
+}
+
+struct Field {
+    name: String,
+    ty: String,
+    file: bool,
+    /// A list of files, sent as an array of uploads
+    file_list: bool,
+    /// Rust expression of the app's default, for optional parameters
+    default: Option<String>,
+    /// The app's default as JSON, sent when an optional file is `None`
+    default_json: serde_json::Value,
+    doc: String,
+}
+
+struct Endpoint<'a> {
+    route: &'a str,
+    method: String,
+    type_prefix: String,
+    /// Parameters in the order the app takes them
+    parameters: Vec<Field>,
+    outputs: Vec<Field>,
+}
+
+impl<'a> Endpoint<'a> {
+    fn new(route: &'a str, method: String, parameters: &[ApiData], returns: &[ApiData]) -> Self {
+        let mut names: HashSet<String> = RESERVED_FIELDS.map(String::from).into();
+        let parameters = parameters
+            .iter()
+            .enumerate()
+            .map(|(i, parameter)| {
+                let name = unique(field_name(parameter, "param", i), &mut names);
+                parameter_field(name, parameter)
+            })
+            .collect();
+
+        let mut names: HashSet<String> = RESERVED_FIELDS.map(String::from).into();
+        let outputs = returns
+            .iter()
+            .enumerate()
+            .map(|(i, ret)| {
+                let name = unique(field_name(ret, "output", i), &mut names);
+                let ty = if ret.is_file() {
+                    "Option<gradio::GradioFileData>".to_string()
+                } else {
+                    value_type(ret)
+                };
+                Field {
+                    name,
+                    ty,
+                    file: false,
+                    file_list: false,
+                    default: None,
+                    default_json: serde_json::Value::Null,
+                    doc: doc(ret),
+                }
+            })
+            .collect();
+
+        Self {
+            route,
+            type_prefix: pascal_case(&method),
+            method,
+            parameters,
+            outputs,
+        }
+    }
+
+    fn required(&self) -> impl Iterator<Item = &Field> {
+        self.parameters
+            .iter()
+            .filter(|field| field.default.is_none())
+    }
+
+    fn optional(&self) -> impl Iterator<Item = &Field> {
+        self.parameters
+            .iter()
+            .filter(|field| field.default.is_some())
+    }
+
+    fn output_type(&self) -> String {
+        if self.outputs.is_empty() {
+            "()".to_string()
+        } else {
+            format!("{}Output", self.type_prefix)
+        }
+    }
+
+    fn write_method(&self, out: &mut String) {
+        let _ = writeln!(out, "\n    /// Call `{}`", self.route);
+        if self.required().next().is_some() {
+            out.push_str("    ///\n");
+            for field in self.required() {
+                let _ = writeln!(out, "    /// - `{}`: {}", field.name, field.doc);
+            }
+        }
+
+        let mut arguments = vec!["&self".to_string()];
+        for field in self.required() {
+            let ty = match field.ty.as_str() {
+                "String" => "impl Into<String>".to_string(),
+                "std::path::PathBuf" => "impl Into<std::path::PathBuf>".to_string(),
+                ty => ty.to_string(),
+            };
+            arguments.push(format!("{}: {}", field.name, ty));
+        }
+        if self.optional().next().is_some() {
+            arguments.push(format!("options: {}Options", self.type_prefix));
+        }
+        let signature = format!(
+            "    pub async fn {}({}) -> Result<{}> {{",
+            self.method,
+            arguments.join(", "),
+            self.output_type()
+        );
+        if signature.len() <= 100 {
+            let _ = writeln!(out, "{}", signature);
+        } else {
+            let _ = writeln!(out, "    pub async fn {}(", self.method);
+            for argument in &arguments {
+                let _ = writeln!(out, "        {},", argument);
+            }
+            let _ = writeln!(out, "    ) -> Result<{}> {{", self.output_type());
+        }
+
+        let inputs: Vec<String> = self.parameters.iter().map(input_expression).collect();
+        let line = format!("        let data = vec![{}];", inputs.join(", "));
+        if line.len() <= 100 && !line.contains('\n') {
+            let _ = writeln!(out, "{}", line);
+        } else {
+            out.push_str("        let data = vec![\n");
+            for input in &inputs {
+                let _ = writeln!(out, "            {},", input);
+            }
+            out.push_str("        ];\n");
+        }
+
+        if self.outputs.is_empty() {
+            let _ = writeln!(
+                out,
+                "        self.client.predict({:?}, data).await?;\n        Ok(())\n    }}",
+                self.route
+            );
+            return;
+        }
+        let _ = writeln!(
+            out,
+            "        let outputs = self.client.predict({:?}, data).await?;",
+            self.route
+        );
+        let _ = writeln!(out, "        Ok({} {{", self.output_type());
+        for (i, field) in self.outputs.iter().enumerate() {
+            let _ = writeln!(out, "            {}: output(&outputs, {})?,", field.name, i);
+        }
+        out.push_str("        })\n    }\n");
+    }
+
+    fn write_types(&self, out: &mut String) {
+        if self.optional().next().is_some() {
+            let name = format!("{}Options", self.type_prefix);
+            let _ = writeln!(
+                out,
+                "\n/// Optional parameters of `{}`, defaulting to the values of the app",
+                self.route
+            );
+            let _ = writeln!(out, "#[derive(Clone, Debug)]\npub struct {} {{", name);
+            for field in self.optional() {
+                let _ = writeln!(out, "    /// {}", field.doc);
+                let _ = writeln!(out, "    pub {}: {},", field.name, field.ty);
+            }
+            out.push_str("}\n");
+
+            let _ = writeln!(out, "\nimpl Default for {} {{", name);
+            out.push_str("    fn default() -> Self {\n        Self {\n");
+            for field in self.optional() {
+                let default = field.default.as_deref().unwrap_or_default();
+                let _ = writeln!(out, "            {}: {},", field.name, default);
+            }
+            out.push_str("        }\n    }\n}\n");
+        }
+
+        if !self.outputs.is_empty() {
+            let _ = writeln!(out, "\n/// Outputs of `{}`", self.route);
+            let _ = writeln!(
+                out,
+                "#[derive(Clone, Debug)]\npub struct {} {{",
+                self.output_type()
+            );
+            for field in &self.outputs {
+                let _ = writeln!(out, "    /// {}", field.doc);
+                let _ = writeln!(out, "    pub {}: {},", field.name, field.ty);
+            }
+            out.push_str("}\n");
+        }
+    }
+}
+
+/// The expression turning a parameter into a `PredictionInput`
+fn input_expression(field: &Field) -> String {
+    if field.default.is_none() {
+        let value = match field.ty.as_str() {
+            "String" | "std::path::PathBuf" => format!("{}.into()", field.name),
+            _ => field.name.clone(),
+        };
+        if field.file {
+            format!("PredictionInput::from_file({})", value)
+        } else if field.file_list {
+            format!(
+                "PredictionInput::Array({}.into_iter().map(PredictionInput::from_file).collect())",
+                value
+            )
+        } else {
+            format!("PredictionInput::from_value({})", value)
+        }
+    } else if field.file_list {
+        format!(
+            "match options.{} {{
+                Some(paths) => {{
+                    PredictionInput::Array(paths.into_iter().map(PredictionInput::from_file).collect())
+                }}
+                None => PredictionInput::Value({}),
+            }}",
+            field.name,
+            json_literal(&field.default_json)
+        )
+    } else if field.file {
+        format!(
+            "match options.{} {{
+                Some(path) => PredictionInput::from_file(path),
+                None => PredictionInput::Value({}),
+            }}",
+            field.name,
+            json_literal(&field.default_json)
+        )
+    } else {
+        format!("PredictionInput::from_value(options.{})", field.name)
+    }
+}
+
+fn parameter_field(name: String, parameter: &ApiData) -> Field {
+    let file = parameter.is_file();
+    let file_list = !file && parameter.is_file_list();
+    let has_default = parameter.parameter_has_default == Some(true);
+    let default_json = parameter.parameter_default.clone().unwrap_or_default();
+    let mut ty = if file {
+        "std::path::PathBuf".to_string()
+    } else if file_list {
+        "Vec<std::path::PathBuf>".to_string()
+    } else {
+        value_type(parameter)
+    };
+
+    let default = if !has_default {
+        None
+    } else if file {
+        // the app default is sent as is when no file is given
+        ty = "Option<std::path::PathBuf>".to_string();
+        Some("None".to_string())
+    } else if file_list {
+        ty = "Option<Vec<std::path::PathBuf>>".to_string();
+        Some("None".to_string())
+    } else if default_json.is_null() {
+        ty = format!("Option<{}>", ty);
+        Some("None".to_string())
+    } else {
+        match rust_literal(&ty, &default_json) {
+            Some(literal) => Some(literal),
+            None => {
+                ty = "gradio::serde_json::Value".to_string();
+                Some(json_literal(&default_json))
+            }
+        }
+    };
+
+    Field {
+        name,
+        ty,
+        file,
+        file_list,
+        default,
+        default_json,
+        doc: doc(parameter),
+    }
+}
+
+fn value_type(data: &ApiData) -> String {
+    let scalar = |ty: &str| match ty {
+        "string" => Some("String"),
+        "number" => Some("f64"),
+        "integer" => Some("i64"),
+        "boolean" => Some("bool"),
+        _ => None,
+    };
+    match data.r#type.r#type.as_str() {
+        "array" => {
+            let items = data.r#type.schema.get("items");
+            match items
+                .and_then(|items| items["type"].as_str())
+                .and_then(scalar)
+            {
+                Some(item) => format!("Vec<{}>", item),
+                None => "Vec<gradio::serde_json::Value>".to_string(),
+            }
+        }
+        ty => scalar(ty)
+            .unwrap_or("gradio::serde_json::Value")
+            .to_string(),
+    }
+}
+
+/// The Rust expression of `value` as `ty`, if it fits
+fn rust_literal(ty: &str, value: &serde_json::Value) -> Option<String> {
+    match ty {
+        "String" => value.as_str().map(|s| format!("{:?}.to_string()", s)),
+        "f64" => value.as_f64().map(|n| format!("{:?}", n)),
+        "i64" => value.as_i64().map(|n| n.to_string()),
+        "bool" => value.as_bool().map(|b| b.to_string()),
+        "gradio::serde_json::Value" => Some(json_literal(value)),
+        ty => {
+            let item = ty.strip_prefix("Vec<")?.strip_suffix('>')?;
+            let items = value.as_array()?;
+            if items.iter().all(|v| rust_literal(item, v).is_some()) {
+                Some(json_literal(value))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// A Rust expression deserializing `value`, valid for any type it fits
+fn json_literal(value: &serde_json::Value) -> String {
+    if value.is_null() {
+        return "gradio::serde_json::Value::Null".to_string();
+    }
+    let json = value.to_string();
+    let hashes = "#".repeat(longest_hash_run(&json) + 1);
+    format!(
+        "gradio::serde_json::from_str(r{}\"{}\"{}).expect(\"valid default\")",
+        hashes, json, hashes
+    )
+}
+
+fn longest_hash_run(text: &str) -> usize {
+    text.split(|c| c != '#')
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+fn doc(data: &ApiData) -> String {
+    let mut doc = format!(
+        "{} ({})",
+        data.label.as_deref().or(data.name()).unwrap_or("unnamed"),
+        data.component
+    );
+    if !data.r#type.description.is_empty() {
+        let _ = write!(doc, ": {}", data.r#type.description);
+    }
+    doc
+}
+
+fn field_name(data: &ApiData, fallback: &str, index: usize) -> String {
+    match data.name().map(snake_case) {
+        Some(name) if !name.is_empty() => name,
+        _ => format!("{}_{}", fallback, index),
+    }
+}
+
+/// Append a number until the name is not taken yet
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut n = 1;
+    while taken.contains(&candidate) {
+        candidate = format!("{}_{}", name, n);
+        n += 1;
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
+
+fn words(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+fn snake_case(name: &str) -> String {
+    let name = words(name).join("_");
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    let name: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Endpoint{}", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{generate, pascal_case, snake_case, CodegenOptions};
+    use crate::structs::ApiInfo;
+
+    #[test]
+    fn names_endpoints_and_fields() {
+        assert_eq!(snake_case("/predict"), "predict");
+        assert_eq!(snake_case("Guidance Scale"), "guidance_scale");
+        assert_eq!(snake_case("type"), "type_");
+        assert_eq!(snake_case("/2x"), "_2x");
+        assert_eq!(
+            pascal_case("stable-diffusion-3-medium"),
+            "StableDiffusion3Medium"
+        );
+        assert_eq!(
+            CodegenOptions::new("stabilityai/stable-diffusion-3-medium").struct_name,
+            "StableDiffusion3Medium"
+        );
+    }
+
+    #[test]
+    fn puts_parameters_with_defaults_in_options() {
+        let api_info: ApiInfo = serde_json::from_value(json!({
+            "named_endpoints": {
+                "/infer": {
+                    "parameters": [
+                        {
+                            "label": "Prompt",
+                            "parameter_name": "prompt",
+                            "parameter_has_default": false,
+                            "component": "Textbox",
+                            "type": {"type": "string"},
+                            "python_type": {"type": "str", "description": ""}
+                        },
+                        {
+                            "label": "Steps",
+                            "parameter_name": "steps",
+                            "parameter_has_default": true,
+                            "parameter_default": 28,
+                            "component": "Slider",
+                            "type": {"type": "number"},
+                            "python_type": {"type": "float", "description": ""}
+                        }
+                    ],
+                    "returns": []
+                }
+            }
+        }))
+        .unwrap();
+
+        let source = generate(&api_info, &CodegenOptions::new("user/app"));
+        assert!(source.contains(
+            "pub async fn infer(&self, prompt: impl Into<String>, options: InferOptions) -> Result<()> {"
+        ));
+        assert!(source.contains("    pub steps: f64,\n"));
+        assert!(source.contains("            steps: 28.0,\n"));
+    }
+
+    #[test]
+    fn takes_file_lists_as_paths() {
+        let file_data = json!({
+            "properties": {
+                "path": {"type": "string"},
+                "meta": {"default": {"_type": "gradio.FileData"}}
+            },
+            "type": "object"
+        });
+        let api_info: ApiInfo = serde_json::from_value(json!({
+            "named_endpoints": {
+                "/merge": {
+                    "parameters": [
+                        {
+                            "label": "Files",
+                            "parameter_name": "files",
+                            "parameter_has_default": false,
+                            "component": "File",
+                            "type": {"type": "array", "items": file_data},
+                            "python_type": {"type": "List[filepath]", "description": ""}
+                        },
+                        {
+                            "label": "Extra",
+                            "parameter_name": "extra",
+                            "parameter_has_default": true,
+                            "parameter_default": null,
+                            "component": "File",
+                            "type": {"type": "array", "items": file_data},
+                            "python_type": {"type": "List[filepath]", "description": ""}
+                        }
+                    ],
+                    "returns": []
+                }
+            }
+        }))
+        .unwrap();
+
+        let source = generate(&api_info, &CodegenOptions::new("user/app"));
+        assert!(source.contains("files: Vec<std::path::PathBuf>, options: MergeOptions"));
+        assert!(source.contains("    pub extra: Option<Vec<std::path::PathBuf>>,\n"));
+        assert!(source.contains(
+            "PredictionInput::Array(files.into_iter().map(PredictionInput::from_file).collect())"
+        ));
+    }
+}
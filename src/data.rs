@@ -1,12 +1,20 @@
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     future::Future,
     path::{Path, PathBuf},
     pin::Pin,
+    sync::Mutex,
 };
 use tokio::io::AsyncWriteExt;
 
-use crate::{constants::UPLOAD_URL, structs::QueueDataMessageOutput, Error, Result};
+use crate::{
+    cache::sha256_hex,
+    constants::UPLOAD_URL,
+    structs::QueueDataMessageOutput,
+    trace::{debug, info_span, Instrument},
+    Error, Result,
+};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum PredictionInput {
@@ -23,6 +31,112 @@ impl PredictionInput {
     pub fn from_value(value: impl serde::Serialize) -> Self {
         Self::Value(serde_json::to_value(value).unwrap())
     }
+
+    /// A file the server downloads from `url` itself, instead of uploading it
+    pub fn from_url(url: impl Into<String>) -> Self {
+        let url = url.into();
+        let orig_name = url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit('/').next())
+            .unwrap_or_default()
+            .to_string();
+        Self::Value(serde_json::json!({
+            "path": url,
+            "orig_name": orig_name,
+            "meta": {"_type": "gradio.FileData"},
+        }))
+    }
+}
+
+/// How much of a file is read to check its type before uploading
+#[cfg(feature = "sniff")]
+const SNIFF_LENGTH: u64 = 8192;
+
+/// What an app accepts as a file parameter, checked before uploading
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileConstraints {
+    /// The `max_file_size` of the app, in bytes
+    pub max_file_size: Option<u64>,
+    /// The `file_types` of the component, extensions such as `.pdf` or
+    /// categories such as `image`, `None` when any file is accepted
+    pub file_types: Option<Vec<String>>,
+}
+
+impl FileConstraints {
+    /// Check a file of `size` bytes whose content is of `mime_type`
+    pub fn check(&self, path: &Path, size: u64, mime_type: &str) -> Result<()> {
+        if let Some(max_size) = self.max_file_size {
+            if size > max_size {
+                return Err(Error::FileTooLarge {
+                    path: path.to_path_buf(),
+                    size,
+                    max_size,
+                });
+            }
+        }
+        if let Some(file_types) = &self.file_types {
+            if !file_types
+                .iter()
+                .any(|file_type| is_file_type(path, mime_type, file_type))
+            {
+                return Err(Error::FileTypeNotAccepted {
+                    path: path.to_path_buf(),
+                    accepted: file_types.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Match a file like Gradio does, by extension or by the category of its mime type
+fn is_file_type(path: &Path, mime_type: &str, file_type: &str) -> bool {
+    if file_type == "file" {
+        return true;
+    }
+    if let Some(extension) = file_type.strip_prefix('.') {
+        return path
+            .extension()
+            .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case(extension));
+    }
+    mime_type.split('/').next() == Some(file_type)
+}
+
+/// Check every file in `data` against the constraints of its parameter, so
+/// nothing is uploaded when one of them would be rejected
+pub async fn check_files(data: &[PredictionInput], constraints: &[FileConstraints]) -> Result<()> {
+    let mut files = vec![];
+    for (input, constraints) in data.iter().zip(constraints) {
+        collect_files(input, constraints, &mut files);
+    }
+    for (path, constraints) in files {
+        let size = tokio::fs::metadata(path).await?.len();
+        // typed like the upload will be, which only reads the file with `sniff`
+        #[cfg(feature = "sniff")]
+        let header = file_header(path).await?;
+        #[cfg(not(feature = "sniff"))]
+        let header = vec![];
+        let mime_type = mime_type(path, &header);
+        constraints.check(path, size, &mime_type)?;
+    }
+    Ok(())
+}
+
+fn collect_files<'a>(
+    input: &'a PredictionInput,
+    constraints: &'a FileConstraints,
+    files: &mut Vec<(&'a Path, &'a FileConstraints)>,
+) {
+    match input {
+        PredictionInput::File(path) => files.push((path, constraints)),
+        PredictionInput::Array(values) => {
+            for value in values {
+                collect_files(value, constraints, files);
+            }
+        }
+        PredictionInput::Value(_) => {}
+    }
 }
 
 pub async fn upload_file(
@@ -30,16 +144,56 @@ pub async fn upload_file(
     api_root: &str,
     path: PathBuf,
 ) -> Result<serde_json::Value> {
+    let bytes = tokio::fs::read(&path).await?;
+    let (file_name, mime_type) = file_name_and_mime_type(&path, &bytes)?;
+    upload_bytes(http_client, api_root, bytes, file_name, mime_type).await
+}
+
+fn file_name_and_mime_type(path: &Path, bytes: &[u8]) -> Result<(String, String)> {
     let file_name = path
         .file_name()
         .ok_or(Error::InvalidFilePath)?
         .to_string_lossy()
         .to_string();
-    let mime_type = mime_guess::from_path(&path)
+    Ok((file_name, mime_type(path, bytes)))
+}
+
+/// The start of a file, enough to sniff its type
+#[cfg(feature = "sniff")]
+async fn file_header(path: &Path) -> Result<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let mut header = vec![];
+    tokio::fs::File::open(path)
+        .await?
+        .take(SNIFF_LENGTH)
+        .read_to_end(&mut header)
+        .await?;
+    Ok(header)
+}
+
+/// The mime type of a file, from its content with the `sniff` feature and
+/// otherwise from its extension
+#[cfg_attr(not(feature = "sniff"), allow(unused_variables))]
+fn mime_type(path: &Path, bytes: &[u8]) -> String {
+    #[cfg(feature = "sniff")]
+    if let Some(kind) = infer::get(bytes) {
+        return kind.mime_type().to_string();
+    }
+    mime_guess::from_path(path)
         .first_or_octet_stream()
         .essence_str()
-        .to_string();
-    let part = reqwest::multipart::Part::bytes(tokio::fs::read(&path).await?)
+        .to_string()
+}
+
+async fn upload_bytes(
+    http_client: &reqwest::Client,
+    api_root: &str,
+    bytes: Vec<u8>,
+    file_name: String,
+    mime_type: String,
+) -> Result<serde_json::Value> {
+    let part = reqwest::multipart::Part::bytes(bytes)
         .file_name(file_name.clone())
         .mime_str(&mime_type)?;
     let form = reqwest::multipart::Form::new().part("files", part);
@@ -56,8 +210,12 @@ pub async fn upload_file(
         return Err(Error::InvalidFileUploadResponse);
     }
 
-    let json = serde_json::json!({
-        "path": res[0],
+    Ok(uploaded_file_data(&res[0], &file_name, &mime_type))
+}
+
+fn uploaded_file_data(server_path: &str, file_name: &str, mime_type: &str) -> serde_json::Value {
+    serde_json::json!({
+        "path": server_path,
         "url": serde_json::Value::Null,
         "orig_name": file_name,
         "mime_type": mime_type,
@@ -65,9 +223,102 @@ pub async fn upload_file(
         "meta": {
             "_type": "gradio.FileData"
         }
-    });
+    })
+}
 
-    Ok(json)
+/// Remembers uploaded files by content hash, so the same file sent in many
+/// predictions is only uploaded once per `api_root`.
+#[derive(Debug, Default)]
+pub struct UploadCache {
+    entries: Mutex<HashMap<(String, String), String>>,
+    /// Serve only known files without asking the server, used when replaying
+    offline: bool,
+}
+
+impl UploadCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A cache that never touches the network, failing on unknown content
+    pub(crate) fn offline(entries: impl IntoIterator<Item = ((String, String), String)>) -> Self {
+        Self {
+            entries: Mutex::new(entries.into_iter().collect()),
+            offline: true,
+        }
+    }
+
+    /// Server paths of the files uploaded to `api_root`, keyed by content hash
+    pub(crate) fn entries(&self, api_root: &str) -> Vec<(String, String)> {
+        self.lock()
+            .iter()
+            .filter(|((root, _), _)| root == api_root)
+            .map(|((_, hash), server_path)| (hash.clone(), server_path.clone()))
+            .collect()
+    }
+
+    /// Forget every uploaded file
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Upload a file, or reuse the server path of an earlier upload with the same
+    /// content if the server still has it
+    pub async fn upload(
+        &self,
+        http_client: &reqwest::Client,
+        api_root: &str,
+        path: PathBuf,
+    ) -> Result<serde_json::Value> {
+        let bytes = tokio::fs::read(&path).await?;
+        let (file_name, mime_type) = file_name_and_mime_type(&path, &bytes)?;
+        let key = (api_root.to_string(), sha256_hex(&bytes));
+
+        let cached = self.lock().get(&key).cloned();
+        if let Some(server_path) = cached {
+            if self.offline || server_has_file(http_client, api_root, &server_path).await? {
+                debug!("Reusing uploaded file {}", server_path);
+                return Ok(uploaded_file_data(&server_path, &file_name, &mime_type));
+            }
+            self.lock().remove(&key);
+        }
+        if self.offline {
+            return Err(Error::CassetteMismatch {
+                message: format!("no recorded upload of {}", path.display()),
+            });
+        }
+
+        let json = upload_bytes(http_client, api_root, bytes, file_name, mime_type).await?;
+        if let Some(server_path) = json["path"].as_str() {
+            self.lock().insert(key, server_path.to_string());
+        }
+        Ok(json)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, String), String>> {
+        self.entries.lock().expect("upload cache lock poisoned")
+    }
+}
+
+async fn server_has_file(
+    http_client: &reqwest::Client,
+    api_root: &str,
+    server_path: &str,
+) -> Result<bool> {
+    let res = http_client
+        .get(format!("{}/file={}", api_root, server_path))
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await?;
+    Ok(res.status().is_success())
 }
 
 pub async fn preprocess_data(
@@ -75,24 +326,50 @@ pub async fn preprocess_data(
     api_root: &str,
     data: Vec<PredictionInput>,
 ) -> Result<Vec<serde_json::Value>> {
-    preprocess_data_helper(http_client, api_root, data).await
+    preprocess_data_helper(http_client, api_root, data, None).await
+}
+
+/// Like [`preprocess_data`], but reuses earlier uploads of the same files
+pub async fn preprocess_data_with_cache(
+    http_client: &reqwest::Client,
+    api_root: &str,
+    data: Vec<PredictionInput>,
+    uploads: &UploadCache,
+) -> Result<Vec<serde_json::Value>> {
+    preprocess_data_helper(http_client, api_root, data, Some(uploads)).await
 }
 
 fn preprocess_data_helper<'a>(
     http_client: &'a reqwest::Client,
     api_root: &'a str,
     data: Vec<PredictionInput>,
-) -> Pin<Box<dyn Future<Output = Result<Vec<serde_json::Value>>> + 'a>> {
+    uploads: Option<&'a UploadCache>,
+) -> Pin<Box<dyn Future<Output = Result<Vec<serde_json::Value>>> + Send + 'a>> {
     Box::pin(async move {
         let mut inputs = vec![];
         for d in data {
             match d {
                 PredictionInput::Value(value) => inputs.push(value),
                 PredictionInput::File(path) => {
-                    inputs.push(upload_file(http_client, api_root, path).await?);
+                    let span = info_span!("upload", path = %path.display());
+                    let uploaded = match uploads {
+                        Some(uploads) => {
+                            uploads
+                                .upload(http_client, api_root, path)
+                                .instrument(span)
+                                .await?
+                        }
+                        None => {
+                            upload_file(http_client, api_root, path)
+                                .instrument(span)
+                                .await?
+                        }
+                    };
+                    inputs.push(uploaded);
                 }
                 PredictionInput::Array(values) => {
-                    let array = preprocess_data(http_client, api_root, values).await?;
+                    let array =
+                        preprocess_data_helper(http_client, api_root, values, uploads).await?;
                     inputs.push(serde_json::json!(array));
                 }
             }
@@ -130,6 +407,15 @@ impl PredictionOutput {
             _ => Err(Error::ExpectedValueOutput),
         }
     }
+
+    /// Deserialize the output into `T`, files as [`GradioFileData`]
+    pub fn decode<T: serde::de::DeserializeOwned>(self) -> Result<T> {
+        let value = match self {
+            Self::File(file) => serde_json::to_value(file)?,
+            Self::Value(value) => value,
+        };
+        Ok(serde_json::from_value(value)?)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -142,6 +428,10 @@ pub struct GradioFileData {
     pub mime_type: Option<String>,
     #[serde(default)]
     pub is_stream: bool,
+    /// Where a [`ResultCache`](crate::ResultCache) stored the file, never
+    /// taken from the server
+    #[serde(skip)]
+    pub(crate) cached_path: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -175,10 +465,20 @@ impl GradioFileData {
         } else {
             reqwest::Client::new()
         };
+        if let Some(path) = &self.cached_path {
+            return Ok(tokio::fs::read(path).await?.into());
+        }
         if let Some(url) = &self.url {
-            let response = http_client.get(url).send().await?;
-            let content = response.bytes().await?;
-            Ok(content)
+            // local files are only read from the cache, whatever URL the app returns
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(Error::UnsupportedFileUrl { url: url.clone() });
+            }
+            async {
+                let response = http_client.get(url).send().await?;
+                Ok(response.bytes().await?)
+            }
+            .instrument(info_span!("download", url = %url))
+            .await
         } else {
             Err(Error::NoFileUrl)
         }
@@ -213,3 +513,430 @@ impl GradioFileData {
         ext.unwrap_or("bin")
     }
 }
+
+/// A message of a `gr.Chatbot`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChatMessage {
+    /// `user` or `assistant`
+    pub role: String,
+    pub content: ChatContent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl ChatMessage {
+    pub fn user(content: impl Into<ChatContent>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+            metadata: None,
+        }
+    }
+
+    pub fn assistant(content: impl Into<ChatContent>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: content.into(),
+            metadata: None,
+        }
+    }
+
+    /// The text of the message, joining the text parts of multimodal content
+    pub fn text(&self) -> Option<String> {
+        match &self.content {
+            ChatContent::Text(text) => Some(text.clone()),
+            ChatContent::Parts(parts) => {
+                let texts: Vec<&str> = parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ChatContentPart::Text { text } => Some(text.as_str()),
+                        ChatContentPart::File { .. } => None,
+                    })
+                    .collect();
+                (!texts.is_empty()).then(|| texts.join("\n"))
+            }
+            ChatContent::File(_) | ChatContent::Component(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ChatContent {
+    Text(String),
+    /// Gradio 6 content made of text and file parts
+    Parts(Vec<ChatContentPart>),
+    File(ChatFile),
+    /// A component embedded in the chat, such as a `gr.Plot`
+    Component(serde_json::Value),
+}
+
+impl From<&str> for ChatContent {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for ChatContent {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChatFile {
+    pub file: GradioFileData,
+    pub alt_text: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChatContentPart {
+    Text { text: String },
+    File { file: GradioFileData },
+}
+
+/// An image or video of a `gr.Gallery`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GalleryItem {
+    pub image: Option<GradioFileData>,
+    pub video: Option<GradioFileData>,
+    pub caption: Option<String>,
+}
+
+impl GalleryItem {
+    pub fn file(&self) -> Option<&GradioFileData> {
+        self.image.as_ref().or(self.video.as_ref())
+    }
+}
+
+/// The value of a `gr.Dataframe`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Dataframe {
+    pub headers: Vec<String>,
+    pub data: Vec<Vec<serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl Dataframe {
+    /// The values of a column by header
+    pub fn column(&self, header: &str) -> Option<Vec<&serde_json::Value>> {
+        let index = self.headers.iter().position(|h| h == header)?;
+        Some(self.data.iter().filter_map(|row| row.get(index)).collect())
+    }
+}
+
+/// The value of a `gr.AnnotatedImage`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AnnotatedImage {
+    pub image: GradioFileData,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Annotation {
+    /// The mask of the annotation
+    pub image: GradioFileData,
+    pub label: String,
+}
+
+/// A span of a `gr.HighlightedText`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HighlightedSpan {
+    pub token: String,
+    pub class_or_confidence: Option<Highlight>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Highlight {
+    Confidence(f64),
+    Class(String),
+}
+
+/// The value of a `gr.Label`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Label {
+    pub label: Option<StringOrNumber>,
+    #[serde(default)]
+    pub confidences: Option<Vec<Confidence>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum StringOrNumber {
+    String(String),
+    Number(f64),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Confidence {
+    pub label: StringOrNumber,
+    pub confidence: f64,
+}
+
+/// The value of a `gr.Plot`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Plot {
+    /// The plotting library, such as `matplotlib`, `plotly`, `altair` or `bokeh`
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// A base64 image for `matplotlib`, a JSON specification otherwise
+    pub plot: String,
+}
+
+/// The output of a known component, converted from its wire format
+#[derive(Clone, Debug)]
+pub enum ComponentValue {
+    Chatbot(Vec<ChatMessage>),
+    Gallery(Vec<GalleryItem>),
+    Dataframe(Dataframe),
+    AnnotatedImage(AnnotatedImage),
+    HighlightedText(Vec<HighlightedSpan>),
+    Label(Label),
+    Plot(Plot),
+    Model3D(GradioFileData),
+    /// A component without a typed model
+    Other(PredictionOutput),
+}
+
+impl ComponentValue {
+    /// Convert outputs using the components listed in the `returns` of an endpoint
+    pub fn from_outputs(
+        outputs: Vec<PredictionOutput>,
+        returns: &[crate::structs::ApiData],
+    ) -> Result<Vec<Self>> {
+        outputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, output)| match returns.get(index) {
+                Some(api_data) => output.as_component(&api_data.component),
+                None => Ok(Self::Other(output)),
+            })
+            .collect()
+    }
+}
+
+impl PredictionOutput {
+    /// Convert the output by the name of its component, as in `ApiData::component`
+    pub fn as_component(self, component: &str) -> Result<ComponentValue> {
+        // components that were not updated or cleared come back as null
+        if matches!(self, PredictionOutput::Value(serde_json::Value::Null)) {
+            return Ok(ComponentValue::Other(self));
+        }
+        let value = |output: PredictionOutput| match output {
+            PredictionOutput::File(file) => serde_json::to_value(file),
+            PredictionOutput::Value(value) => Ok(value),
+        };
+
+        Ok(match component.to_lowercase().as_str() {
+            "chatbot" => ComponentValue::Chatbot(chat_messages(value(self)?)?),
+            "gallery" => ComponentValue::Gallery(serde_json::from_value(value(self)?)?),
+            "dataframe" => ComponentValue::Dataframe(serde_json::from_value(value(self)?)?),
+            "annotatedimage" => {
+                ComponentValue::AnnotatedImage(serde_json::from_value(value(self)?)?)
+            }
+            "highlightedtext" => {
+                ComponentValue::HighlightedText(serde_json::from_value(value(self)?)?)
+            }
+            "label" => ComponentValue::Label(serde_json::from_value(value(self)?)?),
+            "plot" => ComponentValue::Plot(serde_json::from_value(value(self)?)?),
+            "model3d" => ComponentValue::Model3D(self.as_file()?),
+            _ => ComponentValue::Other(self),
+        })
+    }
+}
+
+/// Parse chatbot history in the messages format, or in the legacy format of
+/// `[user, assistant]` pairs
+fn chat_messages(value: serde_json::Value) -> Result<Vec<ChatMessage>> {
+    let serde_json::Value::Array(items) = value else {
+        return Ok(serde_json::from_value(value)?);
+    };
+    if !items.iter().all(serde_json::Value::is_array) {
+        return Ok(serde_json::from_value(serde_json::Value::Array(items))?);
+    }
+
+    let mut messages = vec![];
+    for pair in items {
+        let [user, assistant]: [Option<ChatContent>; 2] = serde_json::from_value(pair)?;
+        messages.extend(user.map(ChatMessage::user));
+        messages.extend(assistant.map(ChatMessage::assistant));
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use std::path::Path;
+
+    use super::{
+        ChatContent, ComponentValue, FileConstraints, GradioFileData, Highlight, PredictionOutput,
+    };
+    use crate::Error;
+
+    fn output(value: serde_json::Value) -> PredictionOutput {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn keeps_null_outputs_untyped() {
+        for component in [
+            "Chatbot",
+            "Gallery",
+            "Dataframe",
+            "Label",
+            "Plot",
+            "Model3D",
+        ] {
+            assert!(matches!(
+                output(json!(null)).as_component(component).unwrap(),
+                ComponentValue::Other(PredictionOutput::Value(serde_json::Value::Null))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn download_only_fetches_http_urls() {
+        let file: GradioFileData = serde_json::from_value(json!({
+            "path": "/etc/passwd",
+            "url": "file:///etc/passwd",
+            "meta": {"_type": "gradio.FileData"}
+        }))
+        .unwrap();
+        assert!(matches!(
+            file.download(None).await,
+            Err(Error::UnsupportedFileUrl { url }) if url == "file:///etc/passwd"
+        ));
+    }
+
+    #[test]
+    fn converts_chatbot_messages_and_tuples() {
+        let messages = json!([
+            {"role": "user", "content": "Hi", "metadata": null},
+            {"role": "assistant", "content": {"file": {"path": "a.png", "meta": {"_type": "gradio.FileData"}}, "alt_text": null}}
+        ]);
+        let ComponentValue::Chatbot(messages) = output(messages).as_component("Chatbot").unwrap()
+        else {
+            panic!("expected chatbot messages");
+        };
+        assert_eq!(messages[0].text().as_deref(), Some("Hi"));
+        assert!(matches!(messages[1].content, ChatContent::File(_)));
+
+        let tuples = json!([["Hi", "Hello!"], ["Bye", null]]);
+        let ComponentValue::Chatbot(messages) = output(tuples).as_component("chatbot").unwrap()
+        else {
+            panic!("expected chatbot messages");
+        };
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].role, "assistant");
+        assert_eq!(messages[2].text().as_deref(), Some("Bye"));
+    }
+
+    #[test]
+    fn converts_dataframe_label_and_highlights() {
+        let dataframe =
+            json!({"headers": ["name", "age"], "data": [["a", 1], ["b", 2]], "metadata": null});
+        let ComponentValue::Dataframe(dataframe) =
+            output(dataframe).as_component("Dataframe").unwrap()
+        else {
+            panic!("expected a dataframe");
+        };
+        assert_eq!(dataframe.column("age").unwrap(), vec![&json!(1), &json!(2)]);
+
+        let label = json!({"label": "cat", "confidences": [{"label": "cat", "confidence": 0.9}]});
+        let ComponentValue::Label(label) = output(label).as_component("Label").unwrap() else {
+            panic!("expected a label");
+        };
+        assert_eq!(label.confidences.unwrap()[0].confidence, 0.9);
+
+        let spans = json!([{"token": "Rust", "class_or_confidence": "lang"}, {"token": " is", "class_or_confidence": null}]);
+        let ComponentValue::HighlightedText(spans) =
+            output(spans).as_component("Highlightedtext").unwrap()
+        else {
+            panic!("expected highlighted text");
+        };
+        assert_eq!(
+            spans[0].class_or_confidence,
+            Some(Highlight::Class("lang".to_string()))
+        );
+    }
+
+    #[test]
+    fn converts_file_components() {
+        let model = json!({"path": "/tmp/fox.gltf", "url": "https://example.com/file=/tmp/fox.gltf", "meta": {"_type": "gradio.FileData"}});
+        assert!(matches!(
+            output(model).as_component("Model3D").unwrap(),
+            ComponentValue::Model3D(_)
+        ));
+        assert!(matches!(
+            output(json!("text")).as_component("Textbox").unwrap(),
+            ComponentValue::Other(PredictionOutput::Value(_))
+        ));
+    }
+
+    #[test]
+    fn checks_file_constraints() {
+        let constraints = FileConstraints {
+            max_file_size: Some(1024),
+            file_types: Some(vec!["image".to_string(), ".PDF".to_string()]),
+        };
+        assert!(constraints
+            .check(Path::new("cat.png"), 1024, "image/png")
+            .is_ok());
+        assert!(constraints
+            .check(Path::new("cat"), 1024, "image/png")
+            .is_ok());
+        assert!(constraints
+            .check(Path::new("paper.pdf"), 10, "application/pdf")
+            .is_ok());
+        assert!(matches!(
+            constraints.check(Path::new("cat.png"), 1025, "image/png"),
+            Err(Error::FileTooLarge { size: 1025, .. })
+        ));
+        let err = constraints
+            .check(Path::new("notes.txt"), 10, "text/plain")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "file notes.txt is not of a type the app accepts: image, .PDF"
+        );
+        assert!(FileConstraints::default()
+            .check(Path::new("notes.txt"), u64::MAX, "text/plain")
+            .is_ok());
+    }
+
+    #[cfg(feature = "sniff")]
+    #[test]
+    fn sniffs_mime_types_from_content() {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0];
+        assert_eq!(super::mime_type(Path::new("image.bin"), &png), "image/png");
+        assert_eq!(
+            super::mime_type(Path::new("notes.txt"), b"hi"),
+            "text/plain"
+        );
+    }
+
+    #[cfg(feature = "sniff")]
+    #[tokio::test]
+    async fn accepts_sniffed_types_without_an_extension() {
+        let path = std::env::temp_dir().join(format!("gradio-sniff-{}", std::process::id()));
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0];
+        tokio::fs::write(&path, png).await.unwrap();
+
+        let constraints = FileConstraints {
+            max_file_size: None,
+            file_types: Some(vec!["image".to_string()]),
+        };
+        let result = super::check_files(
+            &[super::PredictionInput::File(path.clone())],
+            &[constraints],
+        )
+        .await;
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert!(result.is_ok());
+    }
+}
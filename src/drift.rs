@@ -0,0 +1,511 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::Client,
+    structs::{ApiData, ApiInfo, EndpointInfo},
+    Result,
+};
+
+/// The API of an app at one point in time, to detect later changes with
+/// [`ApiLock::diff`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApiLock {
+    pub app_reference: String,
+    /// The Gradio version of the app when locked
+    pub version: String,
+    pub api_info: ApiInfo,
+}
+
+impl ApiLock {
+    pub fn new(
+        app_reference: impl Into<String>,
+        version: impl Into<String>,
+        api_info: ApiInfo,
+    ) -> Self {
+        Self {
+            app_reference: app_reference.into(),
+            version: version.into(),
+            api_info,
+        }
+    }
+
+    pub fn from_client(client: &Client) -> Self {
+        let snapshot = client.snapshot();
+        Self::new(
+            snapshot.app_reference,
+            snapshot.config.version,
+            snapshot.api_info,
+        )
+    }
+
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = tokio::fs::read(path).await?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// Compare the `current` API against the locked one
+    pub fn diff(&self, current: &ApiInfo) -> ApiDiff {
+        diff_api(&self.api_info, current)
+    }
+}
+
+/// A change between two versions of an API
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiChange {
+    EndpointAdded {
+        route: String,
+    },
+    EndpointRemoved {
+        route: String,
+    },
+    ParameterAdded {
+        route: String,
+        name: String,
+        position: usize,
+        has_default: bool,
+    },
+    ParameterRemoved {
+        route: String,
+        name: String,
+        position: usize,
+    },
+    ParameterMoved {
+        route: String,
+        name: String,
+        from: usize,
+        to: usize,
+    },
+    ParameterTypeChanged {
+        route: String,
+        name: String,
+        from: String,
+        to: String,
+    },
+    /// `None` when the parameter has no default, so it is required
+    DefaultChanged {
+        route: String,
+        name: String,
+        from: Option<serde_json::Value>,
+        to: Option<serde_json::Value>,
+    },
+    ReturnAdded {
+        route: String,
+        position: usize,
+    },
+    ReturnRemoved {
+        route: String,
+        position: usize,
+    },
+    ReturnTypeChanged {
+        route: String,
+        position: usize,
+        from: String,
+        to: String,
+    },
+}
+
+impl ApiChange {
+    /// Whether calls written against the old API can fail or get other outputs
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            ApiChange::EndpointAdded { .. } | ApiChange::ReturnAdded { .. } => false,
+            ApiChange::ParameterAdded { has_default, .. } => !has_default,
+            ApiChange::DefaultChanged { to, .. } => to.is_none(),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let default = |value: &Option<serde_json::Value>| match value {
+            Some(value) => value.to_string(),
+            None => "required".to_string(),
+        };
+        match self {
+            ApiChange::EndpointAdded { route } => write!(f, "{} was added", route),
+            ApiChange::EndpointRemoved { route } => write!(f, "{} was removed", route),
+            ApiChange::ParameterAdded {
+                route,
+                name,
+                position,
+                has_default,
+            } => write!(
+                f,
+                "{}: {} parameter `{}` was added at position {}",
+                route,
+                if *has_default { "optional" } else { "required" },
+                name,
+                position
+            ),
+            ApiChange::ParameterRemoved {
+                route,
+                name,
+                position,
+            } => write!(
+                f,
+                "{}: parameter `{}` at position {} was removed",
+                route, name, position
+            ),
+            ApiChange::ParameterMoved {
+                route,
+                name,
+                from,
+                to,
+            } => write!(
+                f,
+                "{}: parameter `{}` moved from position {} to {}",
+                route, name, from, to
+            ),
+            ApiChange::ParameterTypeChanged {
+                route,
+                name,
+                from,
+                to,
+            } => write!(
+                f,
+                "{}: parameter `{}` changed type from {} to {}",
+                route, name, from, to
+            ),
+            ApiChange::DefaultChanged {
+                route,
+                name,
+                from,
+                to,
+            } => write!(
+                f,
+                "{}: default of `{}` changed from {} to {}",
+                route,
+                name,
+                default(from),
+                default(to)
+            ),
+            ApiChange::ReturnAdded { route, position } => {
+                write!(f, "{}: output {} was added", route, position)
+            }
+            ApiChange::ReturnRemoved { route, position } => {
+                write!(f, "{}: output {} was removed", route, position)
+            }
+            ApiChange::ReturnTypeChanged {
+                route,
+                position,
+                from,
+                to,
+            } => write!(
+                f,
+                "{}: output {} changed type from {} to {}",
+                route, position, from, to
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ApiDiff {
+    pub changes: Vec<ApiChange>,
+}
+
+impl ApiDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(ApiChange::is_breaking)
+    }
+
+    pub fn breaking(&self) -> impl Iterator<Item = &ApiChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+}
+
+/// Compare the visible named endpoints of two versions of an API.
+///
+/// Parameters are matched by name, so reordering is reported as moves, which
+/// break positional inputs. Endpoints with `show_api=False` are left out, like
+/// in generated clients and OpenAPI documents.
+pub fn diff_api(old: &ApiInfo, new: &ApiInfo) -> ApiDiff {
+    let mut routes: Vec<&String> = old
+        .named_endpoints
+        .keys()
+        .chain(new.named_endpoints.keys())
+        .collect();
+    routes.sort();
+    routes.dedup();
+
+    let mut changes = vec![];
+    for route in routes {
+        match (visible_endpoint(old, route), visible_endpoint(new, route)) {
+            (Some(old), Some(new)) => diff_endpoint(route, old, new, &mut changes),
+            (Some(_), None) => changes.push(ApiChange::EndpointRemoved {
+                route: route.clone(),
+            }),
+            (None, Some(_)) => changes.push(ApiChange::EndpointAdded {
+                route: route.clone(),
+            }),
+            (None, None) => {}
+        }
+    }
+    ApiDiff { changes }
+}
+
+fn visible_endpoint<'a>(api: &'a ApiInfo, route: &str) -> Option<&'a EndpointInfo> {
+    api.named_endpoints
+        .get(route)
+        .filter(|endpoint| endpoint.show_api != Some(false))
+}
+
+fn diff_endpoint(
+    route: &str,
+    old: &EndpointInfo,
+    new: &EndpointInfo,
+    changes: &mut Vec<ApiChange>,
+) {
+    let old_names = parameter_names(&old.parameters);
+    let new_names = parameter_names(&new.parameters);
+
+    for (from, name) in old_names.iter().enumerate() {
+        let Some(to) = new_names.iter().position(|new_name| new_name == name) else {
+            changes.push(ApiChange::ParameterRemoved {
+                route: route.to_string(),
+                name: name.clone(),
+                position: from,
+            });
+            continue;
+        };
+        if from != to {
+            changes.push(ApiChange::ParameterMoved {
+                route: route.to_string(),
+                name: name.clone(),
+                from,
+                to,
+            });
+        }
+
+        let (old, new) = (&old.parameters[from], &new.parameters[to]);
+        if type_name(old) != type_name(new) {
+            changes.push(ApiChange::ParameterTypeChanged {
+                route: route.to_string(),
+                name: name.clone(),
+                from: type_name(old),
+                to: type_name(new),
+            });
+        }
+        let (old_default, new_default) = (default(old), default(new));
+        if old_default != new_default {
+            changes.push(ApiChange::DefaultChanged {
+                route: route.to_string(),
+                name: name.clone(),
+                from: old_default,
+                to: new_default,
+            });
+        }
+    }
+
+    for (position, name) in new_names.iter().enumerate() {
+        if !old_names.contains(name) {
+            changes.push(ApiChange::ParameterAdded {
+                route: route.to_string(),
+                name: name.clone(),
+                position,
+                has_default: new.parameters[position].parameter_has_default == Some(true),
+            });
+        }
+    }
+
+    for position in 0..old.returns.len().max(new.returns.len()) {
+        match (old.returns.get(position), new.returns.get(position)) {
+            (Some(old), Some(new)) if type_name(old) != type_name(new) => {
+                changes.push(ApiChange::ReturnTypeChanged {
+                    route: route.to_string(),
+                    position,
+                    from: type_name(old),
+                    to: type_name(new),
+                })
+            }
+            (Some(_), None) => changes.push(ApiChange::ReturnRemoved {
+                route: route.to_string(),
+                position,
+            }),
+            (None, Some(_)) => changes.push(ApiChange::ReturnAdded {
+                route: route.to_string(),
+                position,
+            }),
+            _ => {}
+        }
+    }
+}
+
+/// Names to match parameters by, unnamed ones by position
+fn parameter_names(parameters: &[ApiData]) -> Vec<String> {
+    parameters
+        .iter()
+        .enumerate()
+        .map(|(i, parameter)| {
+            parameter
+                .name()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("#{}", i))
+        })
+        .collect()
+}
+
+fn type_name(data: &ApiData) -> String {
+    format!("{} ({})", data.python_type.r#type, data.r#type.r#type)
+}
+
+fn default(data: &ApiData) -> Option<serde_json::Value> {
+    (data.parameter_has_default == Some(true))
+        .then(|| data.parameter_default.clone().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{diff_api, ApiChange};
+    use crate::structs::ApiInfo;
+
+    fn parameter(name: &str, ty: &str, default: Option<serde_json::Value>) -> serde_json::Value {
+        json!({
+            "label": name,
+            "parameter_name": name,
+            "parameter_has_default": default.is_some(),
+            "parameter_default": default,
+            "component": "Textbox",
+            "type": {"type": ty},
+            "python_type": {"type": ty, "description": ""}
+        })
+    }
+
+    fn api(endpoints: serde_json::Value) -> ApiInfo {
+        serde_json::from_value(json!({"named_endpoints": endpoints})).unwrap()
+    }
+
+    #[test]
+    fn reports_breaking_and_compatible_changes() {
+        let old = api(json!({
+            "/infer": {
+                "parameters": [
+                    parameter("prompt", "string", None),
+                    parameter("seed", "number", Some(json!(0))),
+                    parameter("steps", "number", Some(json!(28)))
+                ],
+                "returns": [parameter("image", "string", None)]
+            },
+            "/legacy": {"parameters": [], "returns": []}
+        }));
+        let new = api(json!({
+            "/infer": {
+                "parameters": [
+                    parameter("prompt", "string", None),
+                    parameter("steps", "number", Some(json!(30))),
+                    parameter("seed", "string", Some(json!(0))),
+                    parameter("style", "string", Some(json!("photo")))
+                ],
+                "returns": [parameter("image", "string", None), parameter("seed", "number", None)]
+            },
+            "/upscale": {"parameters": [], "returns": []}
+        }));
+
+        let diff = diff_api(&old, &new);
+        let route = "/infer".to_string();
+        assert_eq!(
+            diff.changes,
+            vec![
+                ApiChange::ParameterMoved {
+                    route: route.clone(),
+                    name: "seed".to_string(),
+                    from: 1,
+                    to: 2
+                },
+                ApiChange::ParameterTypeChanged {
+                    route: route.clone(),
+                    name: "seed".to_string(),
+                    from: "number (number)".to_string(),
+                    to: "string (string)".to_string()
+                },
+                ApiChange::ParameterMoved {
+                    route: route.clone(),
+                    name: "steps".to_string(),
+                    from: 2,
+                    to: 1
+                },
+                ApiChange::DefaultChanged {
+                    route: route.clone(),
+                    name: "steps".to_string(),
+                    from: Some(json!(28)),
+                    to: Some(json!(30))
+                },
+                ApiChange::ParameterAdded {
+                    route: route.clone(),
+                    name: "style".to_string(),
+                    position: 3,
+                    has_default: true
+                },
+                ApiChange::ReturnAdded { route, position: 1 },
+                ApiChange::EndpointRemoved {
+                    route: "/legacy".to_string()
+                },
+                ApiChange::EndpointAdded {
+                    route: "/upscale".to_string()
+                },
+            ]
+        );
+        assert!(diff.is_breaking());
+        assert_eq!(diff.breaking().count(), 4);
+    }
+
+    #[test]
+    fn appending_optional_parameters_is_compatible() {
+        let old = api(json!({
+            "/predict": {"parameters": [parameter("name", "string", None)], "returns": []}
+        }));
+        let new = api(json!({
+            "/predict": {
+                "parameters": [
+                    parameter("name", "string", None),
+                    parameter("greeting", "string", Some(json!("Hello")))
+                ],
+                "returns": []
+            }
+        }));
+
+        let diff = diff_api(&old, &new);
+        assert_eq!(diff.changes.len(), 1);
+        assert!(!diff.is_breaking());
+        assert_eq!(
+            diff.changes[0].to_string(),
+            "/predict: optional parameter `greeting` was added at position 1"
+        );
+        assert!(diff_api(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn ignores_hidden_endpoints() {
+        let old = api(json!({
+            "/predict": {"parameters": [], "returns": []},
+            "/internal": {"parameters": [], "returns": [], "show_api": false}
+        }));
+        let new = api(json!({
+            "/predict": {"parameters": [], "returns": [], "show_api": false},
+            "/internal_2": {"parameters": [], "returns": [], "show_api": false}
+        }));
+
+        let diff = diff_api(&old, &new);
+        assert_eq!(
+            diff.changes,
+            vec![ApiChange::EndpointRemoved {
+                route: "/predict".to_string()
+            }]
+        );
+    }
+}
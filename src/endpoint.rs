@@ -0,0 +1,135 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    data::{PredictionInput, PredictionOutput},
+    Result,
+};
+
+/// Inputs of a route, usually declared with [`#[gradio::endpoint]`](crate::endpoint)
+/// and called with [`Client::call`](crate::Client::call)
+pub trait Endpoint {
+    const ROUTE: &'static str;
+    type Output: FromPredictionOutputs;
+
+    /// The inputs in the order of the endpoint parameters
+    fn into_inputs(self) -> Vec<PredictionInput>;
+}
+
+/// A value that can be sent as an endpoint parameter, paths are sent as files
+pub trait IntoPredictionInput {
+    fn into_prediction_input(self) -> PredictionInput;
+}
+
+/// Outputs decoded in order, as a tuple or a struct whose fields follow the
+/// endpoint returns, with files as [`GradioFileData`](crate::GradioFileData)
+pub trait FromPredictionOutputs: Sized {
+    fn from_prediction_outputs(outputs: Vec<PredictionOutput>) -> Result<Self>;
+}
+
+impl<T: serde::de::DeserializeOwned> FromPredictionOutputs for T {
+    fn from_prediction_outputs(outputs: Vec<PredictionOutput>) -> Result<Self> {
+        Ok(serde_json::from_value(serde_json::to_value(outputs)?)?)
+    }
+}
+
+impl IntoPredictionInput for PredictionInput {
+    fn into_prediction_input(self) -> PredictionInput {
+        self
+    }
+}
+
+impl IntoPredictionInput for PathBuf {
+    fn into_prediction_input(self) -> PredictionInput {
+        PredictionInput::File(self)
+    }
+}
+
+impl IntoPredictionInput for &Path {
+    fn into_prediction_input(self) -> PredictionInput {
+        PredictionInput::File(self.to_path_buf())
+    }
+}
+
+impl<T: IntoPredictionInput> IntoPredictionInput for Option<T> {
+    fn into_prediction_input(self) -> PredictionInput {
+        match self {
+            Some(value) => value.into_prediction_input(),
+            None => PredictionInput::Value(serde_json::Value::Null),
+        }
+    }
+}
+
+impl<T: IntoPredictionInput> IntoPredictionInput for Vec<T> {
+    fn into_prediction_input(self) -> PredictionInput {
+        PredictionInput::Array(
+            self.into_iter()
+                .map(IntoPredictionInput::into_prediction_input)
+                .collect(),
+        )
+    }
+}
+
+macro_rules! impl_into_prediction_input_for_values {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoPredictionInput for $ty {
+                fn into_prediction_input(self) -> PredictionInput {
+                    PredictionInput::from_value(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_prediction_input_for_values!(
+    String,
+    &str,
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    f32,
+    f64,
+    serde_json::Value
+);
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::{FromPredictionOutputs, IntoPredictionInput};
+    use crate::data::{GradioFileData, PredictionInput, PredictionOutput};
+
+    #[test]
+    fn converts_inputs_and_outputs() {
+        assert!(matches!(
+            PathBuf::from("a.png").into_prediction_input(),
+            PredictionInput::File(_)
+        ));
+        assert!(matches!(
+            None::<f64>.into_prediction_input(),
+            PredictionInput::Value(serde_json::Value::Null)
+        ));
+        assert!(matches!(
+            vec!["a", "b"].into_prediction_input(),
+            PredictionInput::Array(items) if items.len() == 2
+        ));
+
+        let outputs: Vec<PredictionOutput> = serde_json::from_value(json!([
+            "done",
+            {"path": "/tmp/a.png", "url": "https://example.com/a.png", "meta": {"_type": "gradio.FileData"}}
+        ]))
+        .unwrap();
+        let (text, file) = <(String, GradioFileData)>::from_prediction_outputs(outputs).unwrap();
+        assert_eq!(text, "done");
+        assert_eq!(file.path.as_deref(), Some("/tmp/a.png"));
+    }
+}
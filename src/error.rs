@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, thiserror::Error)]
@@ -21,20 +23,43 @@ pub enum Error {
     AppConfigUnavailable,
     #[error("could not get API info")]
     ApiInfoUnavailable,
+    #[error("{} holds no API info", .path.display())]
+    NoApiInfoInFile { path: PathBuf },
     #[error("invalid route: {route}")]
     InvalidRoute { route: String },
+    #[error("route {route} is not a batched function")]
+    EndpointNotBatched { route: String },
     #[error("cannot join task queue")]
     CannotJoinTaskQueue,
     #[error("stream ended unexpectedly")]
     StreamEndedUnexpectedly,
     #[error("stream ended")]
     StreamEnded,
+    #[error("no recorded interaction matches: {message}")]
+    CassetteMismatch { message: String },
+    #[error("endpoint {route} does not accept files, it is not a multimodal chat")]
+    ChatFilesNotSupported { route: String },
+    #[error("session not found on the server")]
+    SessionNotFound,
+    #[error("prediction timed out {phase}")]
+    Timeout { phase: TimeoutPhase },
     #[error("unexpected remote error: {message}")]
     UnexpectedRemoteError { message: String },
     #[error("remote error: {message}")]
     RemoteError { message: String },
     #[error("invalid file path")]
     InvalidFilePath,
+    #[error("file {} is {size} bytes, larger than the {max_size} bytes the app accepts", .path.display())]
+    FileTooLarge {
+        path: PathBuf,
+        size: u64,
+        max_size: u64,
+    },
+    #[error("file {} is not of a type the app accepts: {}", .path.display(), .accepted.join(", "))]
+    FileTypeNotAccepted {
+        path: PathBuf,
+        accepted: Vec<String>,
+    },
     #[error("error uploading file")]
     FileUploadFailed,
     #[error("invalid file upload response")]
@@ -43,8 +68,12 @@ pub enum Error {
     ExpectedFileOutput,
     #[error("expected value output")]
     ExpectedValueOutput,
+    #[error("missing output at index {index}")]
+    MissingOutput { index: usize },
     #[error("no URL available for file")]
     NoFileUrl,
+    #[error("file URL {url} is not http or https")]
+    UnsupportedFileUrl { url: String },
     #[error("could not get space status")]
     SpaceStatusUnavailable,
     #[error("space {space_id} is paused by the author")]
@@ -53,6 +82,10 @@ pub enum Error {
     UnknownRuntimeStage { stage: String, space_id: String },
     #[error("space {space_id} is taking too long to start")]
     SpaceStartupTimeout { space_id: String },
+    #[error("no replica available")]
+    NoReplicaAvailable,
+    #[error("replica {api_root} exposes a different API")]
+    MismatchedReplicaApi { api_root: String },
     #[error("server error: {message}")]
     ServerProtocol { message: String },
     #[error("client error: {message}")]
@@ -82,3 +115,20 @@ pub enum Error {
     #[error("append diff requires string or array values")]
     AppendDiffTypeMismatch,
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeoutPhase {
+    /// The job was still waiting in the queue
+    Queue,
+    /// The job was being processed by the app
+    Processing,
+}
+
+impl std::fmt::Display for TimeoutPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Queue => write!(f, "waiting in queue"),
+            Self::Processing => write!(f, "while processing"),
+        }
+    }
+}
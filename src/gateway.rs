@@ -0,0 +1,407 @@
+//! An OpenAI-compatible `/v1/chat/completions` endpoint in front of a Gradio
+//! chat app, available with the `serve` feature.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::{
+    chat::ChatSession, client::Client, data::ChatMessage, data::PredictionInput,
+    session::generate_session_hash, Result,
+};
+
+#[derive(Clone, Debug)]
+pub struct GatewayOptions {
+    /// The chat endpoint requests are sent to
+    pub route: String,
+    /// The model name reported to clients
+    pub model: String,
+}
+
+/// A request to `/v1/chat/completions`, only the fields a chat app can honor
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChatCompletionRequest {
+    #[serde(default)]
+    pub model: Option<String>,
+    pub messages: Vec<ChatCompletionMessage>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChatCompletionMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<ChatCompletionContent>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ChatCompletionContent {
+    Text(String),
+    Parts(Vec<serde_json::Value>),
+}
+
+impl ChatCompletionContent {
+    /// The text of the content, parts other than text are ignored
+    pub fn text(&self) -> String {
+        match self {
+            ChatCompletionContent::Text(text) => text.clone(),
+            ChatCompletionContent::Parts(parts) => parts
+                .iter()
+                .filter(|part| part["type"] == "text")
+                .filter_map(|part| part["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// A request split into what a chat app takes
+#[derive(Debug)]
+struct ChatTurn {
+    system: Option<String>,
+    history: Vec<ChatMessage>,
+    message: String,
+}
+
+impl TryFrom<Vec<ChatCompletionMessage>> for ChatTurn {
+    type Error = String;
+
+    fn try_from(messages: Vec<ChatCompletionMessage>) -> std::result::Result<Self, String> {
+        let mut system: Vec<String> = vec![];
+        let mut history = vec![];
+        for message in messages {
+            let text = message
+                .content
+                .as_ref()
+                .map(ChatCompletionContent::text)
+                .unwrap_or_default();
+            match message.role.as_str() {
+                "system" | "developer" => system.push(text),
+                "user" => history.push(ChatMessage::user(text)),
+                "assistant" => history.push(ChatMessage::assistant(text)),
+                _ => {}
+            }
+        }
+
+        match history.pop() {
+            Some(last) if last.role == "user" => Ok(Self {
+                system: (!system.is_empty()).then(|| system.join("\n")),
+                history,
+                message: last.text().unwrap_or_default(),
+            }),
+            _ => Err("the last message must be from the user".to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Gateway {
+    client: Client,
+    options: GatewayOptions,
+}
+
+impl Gateway {
+    fn chat(&self, turn: &ChatTurn) -> ChatSession {
+        ChatSession::new(&self.client)
+            .with_route(&self.options.route)
+            .with_history(turn.history.clone())
+            .with_additional_inputs(self.additional_inputs(turn.system.as_deref()))
+    }
+
+    /// Whether the chat endpoint takes earlier messages, through a parameter named `history`
+    fn takes_history(&self) -> bool {
+        self.client
+            .view_api()
+            .named_endpoints
+            .get(&self.options.route)
+            .is_some_and(|endpoint| {
+                endpoint
+                    .parameters
+                    .iter()
+                    .any(|parameter| parameter.parameter_name.as_deref() == Some("history"))
+            })
+    }
+
+    /// Defaults of the additional inputs, with the system prompt in place of
+    /// a parameter named like `system_prompt`
+    fn additional_inputs(&self, system: Option<&str>) -> Vec<PredictionInput> {
+        let api = self.client.view_api();
+        let Some(endpoint) = api.named_endpoints.get(&self.options.route) else {
+            return vec![];
+        };
+        endpoint
+            .parameters
+            .iter()
+            .skip(1)
+            .filter(|parameter| parameter.parameter_name.as_deref() != Some("history"))
+            .map(|parameter| {
+                let name = parameter.parameter_name.as_deref().unwrap_or_default();
+                match system {
+                    Some(system) if name.contains("system") => PredictionInput::from_value(system),
+                    _ => PredictionInput::Value(
+                        parameter
+                            .parameter_default
+                            .clone()
+                            .unwrap_or(serde_json::Value::Null),
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    fn chunk(&self, id: &str, created: u64, delta: serde_json::Value, finish: bool) -> Event {
+        let chunk = json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": self.options.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": if finish { Some("stop") } else { None },
+            }],
+        });
+        Event::default().data(chunk.to_string())
+    }
+}
+
+/// Build the routes of the gateway, `/v1/chat/completions` and `/v1/models`
+pub fn router(client: Client, options: GatewayOptions) -> Router {
+    let gateway = Arc::new(Gateway { client, options });
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/models", get(models))
+        .with_state(gateway)
+}
+
+/// Serve the gateway until the listener fails
+pub async fn serve(client: Client, options: GatewayOptions, address: &str) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    axum::serve(listener, router(client, options)).await?;
+    Ok(())
+}
+
+async fn models(State(gateway): State<Arc<Gateway>>) -> Json<serde_json::Value> {
+    Json(json!({
+        "object": "list",
+        "data": [{
+            "id": gateway.options.model,
+            "object": "model",
+            "created": 0,
+            "owned_by": "gradio",
+        }],
+    }))
+}
+
+async fn chat_completions(
+    State(gateway): State<Arc<Gateway>>,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Response {
+    let turn = match ChatTurn::try_from(request.messages) {
+        Ok(turn) => turn,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    if !turn.history.is_empty() && !gateway.takes_history() {
+        let message = format!(
+            "{} has no history parameter, send only the last user message",
+            gateway.options.route
+        );
+        return error_response(StatusCode::BAD_REQUEST, &message);
+    }
+    let id = format!("chatcmpl-{}", generate_session_hash());
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+
+    if request.stream {
+        return stream_completion(gateway, turn, id, created).into_response();
+    }
+
+    let mut chat = gateway.chat(&turn);
+    match chat.send(turn.message.as_str()).await {
+        Ok(reply) => Json(json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": created,
+            "model": gateway.options.model,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": reply_text(&reply)},
+                "finish_reason": "stop",
+            }],
+        }))
+        .into_response(),
+        Err(err) => error_response(StatusCode::BAD_GATEWAY, &err.to_string()),
+    }
+}
+
+/// Relay the reply text as it is generated, ending with `[DONE]`
+fn stream_completion(
+    gateway: Arc<Gateway>,
+    turn: ChatTurn,
+    id: String,
+    created: u64,
+) -> Sse<impl futures_util::Stream<Item = std::result::Result<Event, Infallible>>> {
+    let (sender, receiver) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let mut chat = gateway.chat(&turn);
+        let role = json!({"role": "assistant", "content": ""});
+        if sender
+            .send(gateway.chunk(&id, created, role, false))
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        let result = match chat.send_streaming(turn.message.as_str()).await {
+            Ok(mut reply) => loop {
+                match reply.next().await {
+                    Some(Ok(delta)) => {
+                        let delta = json!({"content": delta});
+                        if sender
+                            .send(gateway.chunk(&id, created, delta, false))
+                            .await
+                            .is_err()
+                        {
+                            // the client went away
+                            return;
+                        }
+                    }
+                    Some(Err(err)) => break Err(err),
+                    None => break Ok(()),
+                }
+            },
+            Err(err) => Err(err),
+        };
+
+        let last = match result {
+            Ok(()) => gateway.chunk(&id, created, json!({}), true),
+            Err(err) => Event::default().data(
+                json!({"error": {"message": err.to_string(), "type": "upstream_error"}})
+                    .to_string(),
+            ),
+        };
+        let _ = sender.send(last).await;
+        let _ = sender.send(Event::default().data("[DONE]")).await;
+    });
+
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Ok(event), receiver))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn reply_text(reply: &[ChatMessage]) -> String {
+    reply
+        .iter()
+        .filter_map(ChatMessage::text)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    let kind = if status.is_client_error() {
+        "invalid_request_error"
+    } else {
+        "upstream_error"
+    };
+    let body = json!({"error": {"message": message, "type": kind}});
+    (status, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{extract::State, http::StatusCode, Json};
+    use serde_json::json;
+
+    use super::{chat_completions, ChatCompletionRequest, ChatTurn, Gateway, GatewayOptions};
+    use crate::{Cassette, Client};
+
+    #[test]
+    fn splits_openai_messages_into_a_chat_turn() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "gpt-4o",
+            "stream": true,
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello!"},
+                {"role": "user", "content": [{"type": "text", "text": "How are you?"}]}
+            ]
+        }))
+        .unwrap();
+        assert!(request.stream);
+
+        let turn = ChatTurn::try_from(request.messages).unwrap();
+        assert_eq!(turn.system.as_deref(), Some("Be brief."));
+        assert_eq!(turn.message, "How are you?");
+        let history: Vec<_> = turn
+            .history
+            .iter()
+            .map(|message| (message.role.as_str(), message.text().unwrap()))
+            .collect();
+        assert_eq!(
+            history,
+            [
+                ("user", "Hi".to_string()),
+                ("assistant", "Hello!".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_requests_not_ending_with_a_user_message() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "messages": [{"role": "user", "content": "Hi"}, {"role": "assistant", "content": "Hello!"}]
+        }))
+        .unwrap();
+        assert!(ChatTurn::try_from(request.messages).is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_history_for_apps_without_a_history_parameter() {
+        let cassette = Cassette::load("tests/fixtures/chat_state.json")
+            .await
+            .unwrap();
+        let gateway = Arc::new(Gateway {
+            client: Client::from_cassette(cassette).unwrap(),
+            options: GatewayOptions {
+                route: "/chat".to_string(),
+                model: "chat".to_string(),
+            },
+        });
+        assert!(!gateway.takes_history());
+
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "messages": [
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello!"},
+                {"role": "user", "content": "How are you?"}
+            ]
+        }))
+        .unwrap();
+        let response = chat_completions(State(gateway), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
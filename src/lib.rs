@@ -4,21 +4,54 @@
 //! `anyhow` is still re-exported temporarily for downstream compatibility,
 //! but new code should prefer `gradio::Error` and `gradio::Result<T>`.
 //!
+extern crate self as gradio;
+
+pub mod cache;
+pub mod cassette;
+pub mod chat;
 pub mod client;
+pub mod codegen;
 pub mod constants;
 pub mod data;
+pub mod drift;
+pub mod endpoint;
 pub mod error;
+#[cfg(feature = "serve")]
+pub mod gateway;
+pub mod mcp;
+pub mod media;
+pub mod openapi;
+pub mod pool;
+pub mod progress;
+pub mod report;
+pub mod session;
+pub mod snapshot;
 pub mod space;
 pub mod stream;
 pub mod structs;
 pub mod sync;
+mod trace;
 
+pub use cache::*;
+pub use cassette::*;
+pub use chat::*;
 pub use client::*;
 pub use data::*;
+pub use drift::*;
+pub use endpoint::*;
 pub use error::*;
+pub use media::*;
+pub use pool::*;
+pub use progress::*;
+pub use report::*;
+pub use session::*;
+pub use snapshot::*;
 pub use stream::*;
 
+pub use gradio_macros::endpoint;
+
 // Re-export anyhow for downstream compatibility during the error-model transition.
 pub use anyhow;
 pub use serde;
+pub use serde_json;
 pub use tokio;
@@ -0,0 +1,391 @@
+//! A [Model Context Protocol](https://modelcontextprotocol.io) server exposing the
+//! named endpoints of a Gradio app as tools, over newline-delimited JSON-RPC on stdio.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use crate::{
+    client::Client,
+    data::{GradioFileData, PredictionInput, PredictionOutput},
+    structs::EndpointInfo,
+    Result,
+};
+
+const PROTOCOL_VERSION: &str = "2025-06-18";
+const SUPPORTED_PROTOCOL_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+/// The first version with `resource_link` content
+const RESOURCE_LINK_VERSION: &str = "2025-06-18";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug)]
+pub struct McpServer {
+    client: Client,
+    /// Shown to MCP clients, usually the Space ID
+    name: String,
+    /// Directories local file arguments may be read from
+    allowed_dirs: Vec<PathBuf>,
+    /// Agreed on in `initialize`
+    protocol_version: RwLock<String>,
+}
+
+impl McpServer {
+    pub fn new(client: Client, name: impl Into<String>) -> Self {
+        Self {
+            client,
+            name: name.into(),
+            allowed_dirs: vec![],
+            protocol_version: RwLock::new(PROTOCOL_VERSION.to_string()),
+        }
+    }
+
+    /// Let file parameters take local paths inside `dir`, otherwise only URLs are accepted
+    pub fn with_allowed_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.allowed_dirs.push(dir.into());
+        self
+    }
+
+    /// Answer requests from stdin until it closes, tool calls run concurrently
+    pub async fn serve_stdio(self) -> Result<()> {
+        let server = Arc::new(self);
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Value>();
+
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(message) = receiver.recv().await {
+                let mut line = message.to_string();
+                line.push('\n');
+                stdout.write_all(line.as_bytes()).await?;
+                stdout.flush().await?;
+            }
+            std::io::Result::Ok(())
+        });
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let server = server.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                if let Some(response) = server.handle_line(&line).await {
+                    let _ = sender.send(response);
+                }
+            });
+        }
+
+        drop(sender);
+        writer.await.map_err(std::io::Error::other)??;
+        Ok(())
+    }
+
+    /// Handle a raw JSON-RPC message, returns the response if one is due
+    pub async fn handle_line(&self, line: &str) -> Option<Value> {
+        match serde_json::from_str::<Value>(line) {
+            Ok(message) => self.handle(message).await,
+            Err(err) => Some(error_response(Value::Null, PARSE_ERROR, &err.to_string())),
+        }
+    }
+
+    /// Handle a JSON-RPC message, notifications get no response
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned()?;
+        let params = message.get("params").cloned().unwrap_or_default();
+        let result = match message["method"].as_str().unwrap_or_default() {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({"tools": self.tools()})),
+            "tools/call" => self.call_tool(&params).await,
+            method => Err((METHOD_NOT_FOUND, format!("method {} not found", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params["protocolVersion"].as_str().unwrap_or_default();
+        let version = if SUPPORTED_PROTOCOL_VERSIONS.contains(&requested) {
+            requested
+        } else {
+            PROTOCOL_VERSION
+        };
+        *self
+            .protocol_version
+            .write()
+            .unwrap_or_else(|err| err.into_inner()) = version.to_string();
+        json!({
+            "protocolVersion": version,
+            "capabilities": {"tools": {"listChanged": false}},
+            "serverInfo": {"name": self.name, "version": env!("CARGO_PKG_VERSION")},
+        })
+    }
+
+    /// Visible named endpoints, by tool name
+    fn endpoints(&self) -> Vec<(String, String, EndpointInfo)> {
+        let mut endpoints: Vec<_> = self
+            .client
+            .view_api()
+            .named_endpoints
+            .into_iter()
+            .filter(|(_, endpoint)| endpoint.show_api != Some(false))
+            .map(|(route, endpoint)| (tool_name(&route), route, endpoint))
+            .collect();
+        endpoints.sort_by(|a, b| a.1.cmp(&b.1));
+        endpoints
+    }
+
+    fn tools(&self) -> Vec<Value> {
+        self.endpoints()
+            .into_iter()
+            .map(|(name, route, endpoint)| {
+                let mut properties = serde_json::Map::new();
+                let mut required = vec![];
+                for (i, parameter) in endpoint.parameters.iter().enumerate() {
+                    let key = parameter_key(parameter.name(), i);
+                    if parameter.parameter_has_default != Some(true) {
+                        required.push(key.clone());
+                    }
+                    properties.insert(key, parameter.json_schema());
+                }
+
+                let returns: Vec<String> = endpoint
+                    .returns
+                    .iter()
+                    .map(|ret| {
+                        format!(
+                            "{} ({})",
+                            ret.name().unwrap_or("unnamed"),
+                            ret.python_type.r#type
+                        )
+                    })
+                    .collect();
+                json!({
+                    "name": name,
+                    "description": format!(
+                        "Call the {} endpoint of {}. Returns: {}",
+                        route,
+                        self.name,
+                        returns.join(", ")
+                    ),
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    async fn call_tool(&self, params: &Value) -> std::result::Result<Value, (i64, String)> {
+        let name = params["name"].as_str().unwrap_or_default();
+        let Some((_, route, endpoint)) = self
+            .endpoints()
+            .into_iter()
+            .find(|(tool, _, _)| tool == name)
+        else {
+            return Err((INVALID_PARAMS, format!("unknown tool {}", name)));
+        };
+
+        let arguments = params.get("arguments").cloned().unwrap_or_default();
+        let mut data = vec![];
+        for (i, parameter) in endpoint.parameters.iter().enumerate() {
+            let key = parameter_key(parameter.name(), i);
+            let value = match arguments.get(&key) {
+                Some(value) if !value.is_null() => value.clone(),
+                _ if parameter.parameter_has_default == Some(true) => {
+                    parameter.parameter_default.clone().unwrap_or_default()
+                }
+                _ => return Ok(tool_error(&format!("missing argument {}", key))),
+            };
+            data.push(match value {
+                Value::String(location) if parameter.is_file() => {
+                    match self.file_input(&location) {
+                        Ok(input) => input,
+                        Err(message) => return Ok(tool_error(&message)),
+                    }
+                }
+                value => PredictionInput::Value(value),
+            });
+        }
+
+        let resource_links = self
+            .protocol_version
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .as_str()
+            >= RESOURCE_LINK_VERSION;
+        match self.client.predict(&route, data).await {
+            Ok(outputs) => Ok(json!({
+                "content": outputs
+                    .iter()
+                    .map(|output| output_content(output, resource_links))
+                    .collect::<Vec<_>>(),
+                "isError": false,
+            })),
+            Err(err) => Ok(tool_error(&err.to_string())),
+        }
+    }
+
+    /// A URL, or a local path that resolves inside one of the allowed directories
+    fn file_input(&self, location: &str) -> std::result::Result<PredictionInput, String> {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            return Ok(PredictionInput::from_url(location));
+        }
+        if self.allowed_dirs.is_empty() {
+            return Err(format!(
+                "{} is not a URL, local files are not allowed by this server",
+                location
+            ));
+        }
+        let path = Path::new(location)
+            .canonicalize()
+            .map_err(|err| format!("cannot read {}: {}", location, err))?;
+        let allowed = self
+            .allowed_dirs
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .any(|dir| path.starts_with(dir));
+        if !allowed {
+            return Err(format!("{} is outside the allowed directories", location));
+        }
+        Ok(PredictionInput::from_file(path))
+    }
+}
+
+/// Routes with characters MCP does not allow in tool names replaced by `_`
+fn tool_name(route: &str) -> String {
+    route
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "_-.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn parameter_key(name: Option<&str>, index: usize) -> String {
+    name.map(str::to_string)
+        .unwrap_or_else(|| format!("param_{}", index))
+}
+
+/// Values become text, files become links to where the app serves them, or
+/// text with the link for clients older than `resource_link`
+fn output_content(output: &PredictionOutput, resource_links: bool) -> Value {
+    match output {
+        PredictionOutput::File(file) if resource_links => resource_link(file),
+        PredictionOutput::File(file) => {
+            let (uri, name) = file_location(file);
+            json!({"type": "text", "text": format!("{}: {}", name, uri)})
+        }
+        PredictionOutput::Value(Value::String(text)) => json!({"type": "text", "text": text}),
+        PredictionOutput::Value(value) => json!({"type": "text", "text": value.to_string()}),
+    }
+}
+
+/// Where the app serves a file, and its name
+fn file_location(file: &GradioFileData) -> (String, String) {
+    let uri = file.url.clone().or(file.path.clone()).unwrap_or_default();
+    let name = file
+        .orig_name
+        .clone()
+        .or_else(|| uri.rsplit('/').next().map(str::to_string))
+        .unwrap_or_default();
+    (uri, name)
+}
+
+fn resource_link(file: &GradioFileData) -> Value {
+    let (uri, name) = file_location(file);
+    let mime_type = file.mime_type.clone().or_else(|| {
+        mime_guess::from_path(&name)
+            .first()
+            .map(|mime| mime.to_string())
+    });
+    let mut link = json!({"type": "resource_link", "uri": uri, "name": name});
+    if let Some(mime_type) = mime_type {
+        link["mimeType"] = mime_type.into();
+    }
+    if let Some(size) = file.size {
+        link["size"] = size.into();
+    }
+    link
+}
+
+fn tool_error(message: &str) -> Value {
+    json!({"content": [{"type": "text", "text": message}], "isError": true})
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{output_content, tool_name};
+    use crate::data::PredictionOutput;
+
+    #[test]
+    fn sanitizes_tool_names() {
+        assert_eq!(tool_name("/predict"), "predict");
+        assert_eq!(tool_name("/lambda 1"), "lambda_1");
+    }
+
+    #[test]
+    fn returns_files_as_resource_links() {
+        let output: PredictionOutput = serde_json::from_value(json!({
+            "path": "/tmp/gradio/abc/image.webp",
+            "url": "https://example.hf.space/gradio_api/file=/tmp/gradio/abc/image.webp",
+            "orig_name": "image.webp",
+            "size": 1024,
+            "mime_type": null,
+            "meta": {"_type": "gradio.FileData"}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            output_content(&output, true),
+            json!({
+                "type": "resource_link",
+                "uri": "https://example.hf.space/gradio_api/file=/tmp/gradio/abc/image.webp",
+                "name": "image.webp",
+                "mimeType": "image/webp",
+                "size": 1024
+            })
+        );
+    }
+
+    #[test]
+    fn returns_files_as_text_before_resource_links() {
+        let output: PredictionOutput = serde_json::from_value(json!({
+            "path": "/tmp/gradio/abc/image.webp",
+            "url": "https://example.hf.space/gradio_api/file=/tmp/gradio/abc/image.webp",
+            "orig_name": "image.webp",
+            "meta": {"_type": "gradio.FileData"}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            output_content(&output, false),
+            json!({
+                "type": "text",
+                "text": "image.webp: https://example.hf.space/gradio_api/file=/tmp/gradio/abc/image.webp"
+            })
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::Path;
+
+use futures_util::stream::{self, Stream};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    data::GradioFileData,
+    stream::PredictionStream,
+    structs::{QueueDataMessage, QueueDataMessageOutput},
+    Error, Result,
+};
+
+/// Assembles a streamed media output (`is_stream` files) into a sequence of byte chunks.
+///
+/// Streaming audio and video components deliver their output during `process_streaming`
+/// as either an HLS playlist (`.m3u8`) that grows over time, or as one file reference per
+/// chunk. `MediaStream` follows one output of a [`PredictionStream`], downloads every new
+/// segment exactly once and yields the bytes in order.
+pub struct MediaStream {
+    stream: PredictionStream,
+    output_index: usize,
+    seen: HashSet<String>,
+    pending: VecDeque<String>,
+    streamed: bool,
+    finished: bool,
+}
+
+impl MediaStream {
+    /// Follow the output at `output_index` of the given prediction.
+    pub fn new(stream: PredictionStream, output_index: usize) -> Self {
+        Self {
+            stream,
+            output_index,
+            seen: HashSet::new(),
+            pending: VecDeque::new(),
+            streamed: false,
+            finished: false,
+        }
+    }
+
+    /// Get the underlying prediction stream
+    pub fn prediction(&self) -> &PredictionStream {
+        &self.stream
+    }
+
+    /// Download the next media chunk, or `None` once the prediction has completed
+    /// and every chunk has been delivered.
+    pub async fn next_chunk(&mut self) -> Option<Result<bytes::Bytes>> {
+        loop {
+            if let Some(url) = self.pending.pop_front() {
+                return Some(self.download(&url).await);
+            }
+            if self.finished {
+                return None;
+            }
+
+            let message = match self.stream.next().await {
+                Some(Ok(message)) => message,
+                Some(Err(Error::StreamEnded)) | None => {
+                    self.finished = true;
+                    continue;
+                }
+                Some(Err(err)) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            };
+
+            if let Err(err) = self.handle_message(message).await {
+                self.finished = true;
+                return Some(Err(err));
+            }
+        }
+    }
+
+    /// Concatenate every chunk into a single file
+    pub async fn save_to_path(mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(path).await?;
+        while let Some(chunk) = self.next_chunk().await {
+            file.write_all(&chunk?).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Convert into an async stream of byte chunks
+    pub fn into_stream(self) -> impl Stream<Item = Result<bytes::Bytes>> {
+        stream::unfold(self, |mut media| async move {
+            media.next_chunk().await.map(|chunk| (chunk, media))
+        })
+    }
+
+    async fn handle_message(&mut self, message: QueueDataMessage) -> Result<()> {
+        match message {
+            QueueDataMessage::ProcessGenerating {
+                output,
+                success: true,
+                ..
+            }
+            | QueueDataMessage::ProcessStreaming {
+                output,
+                success: true,
+                ..
+            } => {
+                if let Some(file) = self.file_at(&output) {
+                    if file.is_stream {
+                        self.streamed = true;
+                        self.enqueue(&file).await?;
+                    }
+                }
+            }
+            QueueDataMessage::ProcessCompleted { output, .. } => {
+                self.finished = true;
+                if let QueueDataMessageOutput::Error { error, .. } = output {
+                    return Err(Error::RemoteError {
+                        message: error.unwrap_or_else(|| "Unknown error".to_string()),
+                    });
+                }
+                match self.file_at(&output) {
+                    // the final payload repeats the playlist, so poll it once more for the tail
+                    Some(file) if file.is_stream => self.enqueue(&file).await?,
+                    Some(file) if !self.streamed => self.enqueue(&file).await?,
+                    Some(_) => {}
+                    None if !self.streamed => return Err(Error::ExpectedFileOutput),
+                    None => {}
+                }
+            }
+            QueueDataMessage::UnexpectedError { message, .. } => {
+                self.finished = true;
+                return Err(Error::UnexpectedRemoteError {
+                    message: message.unwrap_or_else(|| "Unexpected error".to_string()),
+                });
+            }
+            QueueDataMessage::CloseStream => {
+                self.finished = true;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn file_at(&self, output: &QueueDataMessageOutput) -> Option<GradioFileData> {
+        let QueueDataMessageOutput::Success { data, .. } = output else {
+            return None;
+        };
+        let value = data.get(self.output_index)?;
+        serde_json::from_value::<GradioFileData>(value.clone()).ok()
+    }
+
+    async fn enqueue(&mut self, file: &GradioFileData) -> Result<()> {
+        let url = match (&file.url, &file.path) {
+            (Some(url), _) => url.clone(),
+            (None, Some(path)) => format!("{}/file={}", self.stream.api_root, path),
+            (None, None) => return Err(Error::NoFileUrl),
+        };
+
+        let urls = if is_playlist(&url) {
+            let playlist = self
+                .stream
+                .http_client
+                .get(&url)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            parse_playlist(&url, &playlist)?
+        } else {
+            vec![url]
+        };
+
+        for url in urls {
+            if self.seen.insert(url.clone()) {
+                self.pending.push_back(url);
+            }
+        }
+        Ok(())
+    }
+
+    async fn download(&self, url: &str) -> Result<bytes::Bytes> {
+        let response = self
+            .stream
+            .http_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.bytes().await?)
+    }
+}
+
+fn is_playlist(url: &str) -> bool {
+    url.split(['?', '#'])
+        .next()
+        .is_some_and(|path| path.ends_with(".m3u8"))
+}
+
+fn parse_playlist(playlist_url: &str, playlist: &str) -> Result<Vec<String>> {
+    let base = reqwest::Url::parse(playlist_url).map_err(|err| Error::ServerProtocol {
+        message: format!("invalid playlist url {}: {}", playlist_url, err),
+    })?;
+
+    playlist
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|segment| {
+            base.join(segment)
+                .map(String::from)
+                .map_err(|err| Error::ServerProtocol {
+                    message: format!("invalid playlist segment {}: {}", segment, err),
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_playlist, parse_playlist};
+
+    #[test]
+    fn detects_playlist_urls() {
+        assert!(is_playlist(
+            "https://example.hf.space/gradio_api/stream/abc/0/playlist.m3u8"
+        ));
+        assert!(is_playlist("https://example.com/playlist.m3u8?token=1"));
+        assert!(!is_playlist("https://example.com/file=/tmp/chunk.aac"));
+    }
+
+    #[test]
+    fn resolves_playlist_segments_relative_to_playlist() {
+        let playlist = "#EXTM3U\n#EXT-X-TARGETDURATION:1\n#EXTINF:1.0,\n0.aac\n#EXTINF:1.0,\nhttps://cdn.example.com/1.aac\n#EXT-X-ENDLIST\n";
+        let segments = parse_playlist(
+            "https://example.hf.space/gradio_api/stream/abc/0/playlist.m3u8",
+            playlist,
+        )
+        .unwrap();
+
+        assert_eq!(
+            segments,
+            vec![
+                "https://example.hf.space/gradio_api/stream/abc/0/0.aac".to_string(),
+                "https://cdn.example.com/1.aac".to_string(),
+            ]
+        );
+    }
+}
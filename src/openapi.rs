@@ -0,0 +1,281 @@
+//! Describe the HTTP API of an app as an OpenAPI 3.1 document.
+//!
+//! Each named endpoint is called by posting its inputs to `/call/{api_name}`,
+//! which returns an event ID, then reading the outputs from the event stream at
+//! `/call/{api_name}/{event_id}`.
+//!
+//! `/call` only takes JSON, so files are uploaded as binary to `/upload` first
+//! and passed as `FileData` with the returned server path. The event stream is
+//! not JSON either, the schema of the `complete` event data is given as the
+//! `x-complete-event-data` extension of its response.
+
+use serde_json::{json, Value};
+
+use crate::structs::{ApiData, ApiInfo, EndpointInfo};
+
+const FILE_DATA_REF: &str = "#/components/schemas/FileData";
+
+#[derive(Clone, Debug)]
+pub struct OpenApiOptions {
+    pub title: String,
+    /// Version of the described API, such as the Gradio version of the app
+    pub version: String,
+    /// The API root the paths are relative to
+    pub server_url: Option<String>,
+}
+
+impl OpenApiOptions {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: "1.0.0".to_string(),
+            server_url: None,
+        }
+    }
+
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    pub fn with_server_url(mut self, server_url: impl Into<String>) -> Self {
+        self.server_url = Some(server_url.into());
+        self
+    }
+}
+
+/// Build the OpenAPI document of the visible named endpoints
+pub fn to_openapi(api_info: &ApiInfo, options: &OpenApiOptions) -> Value {
+    let mut routes: Vec<(&String, &EndpointInfo)> = api_info
+        .named_endpoints
+        .iter()
+        .filter(|(_, endpoint)| endpoint.show_api != Some(false))
+        .collect();
+    routes.sort_by_key(|(route, _)| *route);
+
+    let mut paths = serde_json::Map::new();
+    paths.insert("/upload".to_string(), upload_path());
+    for (route, endpoint) in routes {
+        let name = route.trim_start_matches('/');
+        let operation_id = operation_id(name);
+        paths.insert(
+            format!("/call/{}", name),
+            json!({
+                "post": {
+                    "operationId": operation_id,
+                    "summary": format!("Call {}", route),
+                    "requestBody": {
+                        "required": true,
+                        "content": {"application/json": {"schema": {
+                            "type": "object",
+                            "required": ["data"],
+                            "properties": {"data": parameters_schema(endpoint)},
+                        }}},
+                    },
+                    "responses": {"200": {
+                        "description": format!(
+                            "The prediction was queued, read its outputs from /call/{}/{{event_id}}",
+                            name
+                        ),
+                        "content": {"application/json": {"schema": {
+                            "type": "object",
+                            "required": ["event_id"],
+                            "properties": {"event_id": {"type": "string"}},
+                        }}},
+                    }},
+                },
+            }),
+        );
+        paths.insert(
+            format!("/call/{}/{{event_id}}", name),
+            json!({
+                "get": {
+                    "operationId": format!("{}_result", operation_id),
+                    "summary": format!("Stream the outputs of {}", route),
+                    "description": "Server-sent events, the `complete` event carries the outputs as its data, described by `x-complete-event-data`",
+                    "parameters": [{
+                        "name": "event_id",
+                        "in": "path",
+                        "required": true,
+                        "schema": {"type": "string"},
+                    }],
+                    "responses": {"200": {
+                        "description": "The event stream of the prediction",
+                        "content": {"text/event-stream": {"schema": {"type": "string"}}},
+                        "x-complete-event-data": returns_schema(endpoint),
+                    }},
+                },
+            }),
+        );
+    }
+
+    let mut document = json!({
+        "openapi": "3.1.0",
+        "info": {"title": options.title, "version": options.version},
+        "paths": paths,
+        "components": {"schemas": {"FileData": file_data_schema()}},
+    });
+    if let Some(server_url) = &options.server_url {
+        document["servers"] = json!([{"url": server_url}]);
+    }
+    document
+}
+
+/// JSON Schema of the `data` array sent to an endpoint
+pub fn parameters_schema(endpoint: &EndpointInfo) -> Value {
+    let required = endpoint
+        .parameters
+        .iter()
+        .rposition(|parameter| parameter.parameter_has_default != Some(true))
+        .map_or(0, |last| last + 1);
+    let mut schema = tuple_schema(&endpoint.parameters);
+    schema["minItems"] = required.into();
+    schema
+}
+
+/// JSON Schema of the outputs of an endpoint
+pub fn returns_schema(endpoint: &EndpointInfo) -> Value {
+    tuple_schema(&endpoint.returns)
+}
+
+fn tuple_schema(items: &[ApiData]) -> Value {
+    json!({
+        "type": "array",
+        "prefixItems": items.iter().map(schema).collect::<Vec<_>>(),
+        "minItems": items.len(),
+        "maxItems": items.len(),
+    })
+}
+
+/// The schema of a value, with files referring to the shared `FileData`
+fn schema(data: &ApiData) -> Value {
+    if !data.is_file() {
+        return data.json_schema();
+    }
+    let mut schema = json!({"$ref": FILE_DATA_REF});
+    if let Some(label) = &data.label {
+        schema["title"] = label.clone().into();
+    }
+    schema
+}
+
+fn upload_path() -> Value {
+    json!({
+        "post": {
+            "operationId": "upload",
+            "summary": "Upload files to pass to endpoints",
+            "requestBody": {
+                "required": true,
+                "content": {"multipart/form-data": {"schema": {
+                    "type": "object",
+                    "required": ["files"],
+                    "properties": {"files": {
+                        "type": "array",
+                        "items": {"type": "string", "format": "binary"},
+                    }},
+                }}},
+            },
+            "responses": {"200": {
+                "description": "The server paths of the uploaded files, used as `path` of a FileData",
+                "content": {"application/json": {"schema": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                }}},
+            }},
+        },
+    })
+}
+
+fn file_data_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "A file uploaded to /upload, or a URL the server downloads",
+        "required": ["path", "meta"],
+        "properties": {
+            "path": {"type": "string", "description": "Server path from /upload, or a URL"},
+            "url": {"type": ["string", "null"]},
+            "orig_name": {"type": ["string", "null"]},
+            "mime_type": {"type": ["string", "null"]},
+            "size": {"type": ["integer", "null"]},
+            "meta": {
+                "type": "object",
+                "properties": {"_type": {"const": "gradio.FileData"}},
+            },
+        },
+    })
+}
+
+fn operation_id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{to_openapi, OpenApiOptions};
+    use crate::structs::ApiInfo;
+
+    #[test]
+    fn describes_endpoints_as_operations() {
+        let api_info: ApiInfo = serde_json::from_value(json!({
+            "named_endpoints": {
+                "/describe": {
+                    "parameters": [{
+                        "label": "file",
+                        "parameter_name": "file",
+                        "parameter_has_default": false,
+                        "component": "File",
+                        "type": {"type": "object"},
+                        "python_type": {"type": "filepath", "description": ""}
+                    }, {
+                        "label": "Detail",
+                        "parameter_name": "detail",
+                        "parameter_has_default": true,
+                        "parameter_default": false,
+                        "component": "Checkbox",
+                        "type": {"type": "boolean"},
+                        "python_type": {"type": "bool", "description": ""}
+                    }],
+                    "returns": [{
+                        "label": "output",
+                        "component": "Textbox",
+                        "type": {"type": "string"},
+                        "python_type": {"type": "str", "description": ""}
+                    }]
+                }
+            }
+        }))
+        .unwrap();
+
+        let options = OpenApiOptions::new("user/app").with_server_url("https://example.com");
+        let document = to_openapi(&api_info, &options);
+
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["servers"][0]["url"], "https://example.com");
+        let data = &document["paths"]["/call/describe"]["post"]["requestBody"]["content"]
+            ["application/json"]["schema"]["properties"]["data"];
+        assert_eq!(
+            data["prefixItems"][0]["$ref"],
+            "#/components/schemas/FileData"
+        );
+        assert_eq!(data["prefixItems"][1]["default"], false);
+        assert_eq!(data["minItems"], 1);
+        assert_eq!(data["maxItems"], 2);
+
+        let response = &document["paths"]["/call/describe/{event_id}"]["get"]["responses"]["200"];
+        assert_eq!(
+            response["content"]["text/event-stream"]["schema"]["type"],
+            "string"
+        );
+        let outputs = &response["x-complete-event-data"];
+        assert_eq!(outputs["prefixItems"][0]["type"], "string");
+        assert_eq!(
+            document["paths"]["/upload"]["post"]["requestBody"]["content"]["multipart/form-data"]
+                ["schema"]["properties"]["files"]["items"]["format"],
+            "binary"
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{
+    client::{Client, ClientOptions},
+    data::{PredictionInput, PredictionOutput},
+    progress::ProgressPhase,
+    stream::PredictionStream,
+    structs::ApiInfo,
+    Error, Result,
+};
+
+/// How long a replica is skipped after it refused a submission
+const UNAVAILABLE_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoutingStrategy {
+    /// Prefer the replica with the shortest known queue
+    #[default]
+    LeastQueue,
+    /// Cycle through replicas in order
+    RoundRobin,
+}
+
+#[derive(Debug)]
+struct Replica {
+    client: Client,
+    in_flight: AtomicUsize,
+    /// Last queue size reported by an `estimation` message, -1 if unknown
+    queue_size: AtomicI64,
+    unavailable_until: Mutex<Option<Instant>>,
+}
+
+impl Replica {
+    fn new(client: Client) -> Self {
+        Self {
+            client,
+            in_flight: AtomicUsize::new(0),
+            queue_size: AtomicI64::new(-1),
+            unavailable_until: Mutex::new(None),
+        }
+    }
+
+    fn load(&self) -> i64 {
+        self.queue_size.load(Ordering::Relaxed).max(0)
+            + self.in_flight.load(Ordering::Relaxed) as i64
+    }
+
+    fn is_available(&self) -> bool {
+        let until = self
+            .unavailable_until
+            .lock()
+            .expect("replica lock poisoned");
+        until.is_none_or(|until| Instant::now() >= until)
+    }
+
+    fn mark_unavailable(&self) {
+        *self
+            .unavailable_until
+            .lock()
+            .expect("replica lock poisoned") = Some(Instant::now() + UNAVAILABLE_BACKOFF);
+    }
+}
+
+/// Spreads submissions over several replicas of the same app.
+///
+/// All replicas must expose the same API. Submissions are routed by the chosen
+/// [`RoutingStrategy`], and move on to another replica when one cannot join the
+/// queue or cannot be reached.
+#[derive(Debug)]
+pub struct ClientPool {
+    replicas: Vec<Arc<Replica>>,
+    strategy: RoutingStrategy,
+    next: AtomicUsize,
+}
+
+impl ClientPool {
+    /// Connect to every replica, skipping the ones whose Space is paused
+    pub async fn new(app_references: &[&str], options: ClientOptions) -> Result<Self> {
+        let mut clients = vec![];
+        for app_reference in app_references {
+            match Client::new(app_reference, options.clone()).await {
+                Ok(client) => clients.push(client),
+                Err(Error::SpacePaused { .. }) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Self::from_clients(clients)
+    }
+
+    /// Build a pool from connected clients, which must share the same API
+    pub fn from_clients(clients: Vec<Client>) -> Result<Self> {
+        let first = clients.first().ok_or(Error::NoReplicaAvailable)?;
+        let api = first.view_api();
+        for client in &clients[1..] {
+            if !same_api(&api, &client.view_api()) {
+                return Err(Error::MismatchedReplicaApi {
+                    api_root: client.api_root.clone(),
+                });
+            }
+        }
+
+        Ok(Self {
+            replicas: clients
+                .into_iter()
+                .map(|client| Arc::new(Replica::new(client)))
+                .collect(),
+            strategy: RoutingStrategy::default(),
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn with_strategy(mut self, strategy: RoutingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.replicas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replicas.is_empty()
+    }
+
+    pub fn clients(&self) -> Vec<&Client> {
+        self.replicas
+            .iter()
+            .map(|replica| &replica.client)
+            .collect()
+    }
+
+    pub async fn submit(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+    ) -> Result<PredictionStream> {
+        let mut last_error = Error::NoReplicaAvailable;
+        for replica in self.candidates() {
+            match replica.client.submit(route, data.clone()).await {
+                Ok(stream) => {
+                    track(&replica, &stream);
+                    return Ok(stream);
+                }
+                Err(err) if is_unavailable(&err) => {
+                    replica.mark_unavailable();
+                    last_error = err;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_error)
+    }
+
+    pub async fn predict(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+    ) -> Result<Vec<PredictionOutput>> {
+        let mut stream = self.submit(route, data).await?;
+        Client::wait_for_outputs(&mut stream).await
+    }
+
+    /// Replicas in the order they should be tried, unavailable ones last
+    fn candidates(&self) -> Vec<Arc<Replica>> {
+        let mut replicas = self.replicas.clone();
+        match self.strategy {
+            RoutingStrategy::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % replicas.len().max(1);
+                replicas.rotate_left(start);
+            }
+            RoutingStrategy::LeastQueue => {
+                replicas.sort_by_key(|replica| replica.load());
+            }
+        }
+        replicas.sort_by_key(|replica| !replica.is_available());
+        replicas
+    }
+}
+
+/// Follow the progress of a submission to keep the replica's load up to date
+fn track(replica: &Arc<Replica>, stream: &PredictionStream) {
+    let replica = replica.clone();
+    let mut progress = stream.progress();
+    replica.in_flight.fetch_add(1, Ordering::Relaxed);
+
+    tokio::spawn(async move {
+        while progress.changed().await.is_ok() {
+            let state = progress.borrow_and_update().clone();
+            if let Some(queue_size) = state.queue_size {
+                replica.queue_size.store(queue_size, Ordering::Relaxed);
+            }
+            if matches!(
+                state.phase,
+                ProgressPhase::Completed | ProgressPhase::Failed
+            ) {
+                break;
+            }
+        }
+        replica.in_flight.fetch_sub(1, Ordering::Relaxed);
+    });
+}
+
+/// Whether a submission failed because of the replica rather than the request
+fn is_unavailable(err: &Error) -> bool {
+    match err {
+        Error::CannotJoinTaskQueue => true,
+        Error::Http(err) => err.is_connect() || err.is_timeout(),
+        _ => false,
+    }
+}
+
+fn same_api(a: &ApiInfo, b: &ApiInfo) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::structs::{ProcessingProgressData, QueueDataMessage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressPhase {
+    /// Waiting in the queue
+    Queued,
+    /// The app is running the prediction
+    Processing,
+    /// The prediction finished, successfully or not
+    Completed,
+    /// The server reported an unexpected error
+    Failed,
+}
+
+/// The progress of a prediction, aggregated from the queue messages of a stream.
+#[derive(Clone, Debug)]
+pub struct ProgressState {
+    pub phase: ProgressPhase,
+    /// Zero-based position in the queue
+    pub rank: Option<i64>,
+    pub queue_size: Option<i64>,
+    /// Estimated time until the current phase ends, as of `updated_at`
+    pub eta: Option<Duration>,
+    /// Nested `gr.Progress` trackers, outermost first
+    pub levels: Vec<ProcessingProgressData>,
+    /// The processing time limit enforced by the server, if any
+    pub time_limit: Option<Duration>,
+    pub submitted_at: Instant,
+    pub processing_started_at: Option<Instant>,
+    pub updated_at: Instant,
+}
+
+impl ProgressState {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            phase: ProgressPhase::Queued,
+            rank: None,
+            queue_size: None,
+            eta: None,
+            levels: vec![],
+            time_limit: None,
+            submitted_at: now,
+            processing_started_at: None,
+            updated_at: now,
+        }
+    }
+
+    /// Update the state from a queue message, returns whether anything changed
+    pub fn apply(&mut self, message: &QueueDataMessage) -> bool {
+        let now = Instant::now();
+        match message {
+            QueueDataMessage::Estimation {
+                rank,
+                queue_size,
+                rank_eta,
+                ..
+            } => {
+                self.phase = ProgressPhase::Queued;
+                self.rank = Some(*rank);
+                self.queue_size = Some(*queue_size);
+                self.eta = rank_eta.and_then(seconds);
+            }
+            QueueDataMessage::ProcessStarts {
+                eta, progress_data, ..
+            }
+            | QueueDataMessage::Progress {
+                eta, progress_data, ..
+            } => {
+                self.start_processing(now);
+                if eta.is_some() {
+                    self.eta = eta.and_then(seconds);
+                }
+                if let Some(progress_data) = progress_data {
+                    self.levels = progress_data.clone();
+                }
+            }
+            QueueDataMessage::ProcessGenerating {
+                progress_data,
+                time_limit,
+                ..
+            }
+            | QueueDataMessage::ProcessStreaming {
+                progress_data,
+                time_limit,
+                ..
+            } => {
+                self.start_processing(now);
+                if time_limit.is_some() {
+                    self.time_limit = time_limit.and_then(seconds);
+                }
+                if let Some(progress_data) = progress_data {
+                    self.levels = progress_data.clone();
+                }
+            }
+            QueueDataMessage::ProcessCompleted { .. } => {
+                self.phase = ProgressPhase::Completed;
+                self.eta = None;
+            }
+            QueueDataMessage::UnexpectedError { .. } => {
+                self.phase = ProgressPhase::Failed;
+                self.eta = None;
+            }
+            _ => return false,
+        }
+        self.updated_at = now;
+        true
+    }
+
+    fn start_processing(&mut self, now: Instant) {
+        if self.phase == ProgressPhase::Queued {
+            self.phase = ProgressPhase::Processing;
+            self.processing_started_at = Some(now);
+            self.rank = None;
+            self.eta = None;
+        }
+    }
+
+    /// Time since the prediction was submitted
+    pub fn elapsed(&self) -> Duration {
+        self.submitted_at.elapsed()
+    }
+
+    /// Time spent waiting in the queue so far, or in total once processing started
+    pub fn queue_time(&self) -> Duration {
+        match self.processing_started_at {
+            Some(started) => started.duration_since(self.submitted_at),
+            None => self.submitted_at.elapsed(),
+        }
+    }
+
+    /// Time spent processing so far
+    pub fn processing_time(&self) -> Option<Duration> {
+        self.processing_started_at.map(|started| started.elapsed())
+    }
+
+    /// The ETA adjusted for the time passed since it was reported
+    pub fn remaining(&self) -> Option<Duration> {
+        self.eta
+            .map(|eta| eta.saturating_sub(self.updated_at.elapsed()))
+    }
+
+    /// Overall completion between 0 and 1 combining the nested trackers,
+    /// or `None` if the progress is indeterminate
+    pub fn fraction(&self) -> Option<f64> {
+        let mut inner: Option<f64> = None;
+        for level in self.levels.iter().rev() {
+            inner = match (level.progress, level.length) {
+                (Some(progress), _) => Some(progress),
+                (None, Some(length)) if length > 0 => {
+                    Some((level.index as f64 + inner.unwrap_or(0.0)) / length as f64)
+                }
+                _ => inner,
+            };
+        }
+        inner.map(|fraction| fraction.clamp(0.0, 1.0))
+    }
+}
+
+impl Default for ProgressState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for ProgressState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.phase {
+            ProgressPhase::Queued => {
+                write!(f, "Queueing")?;
+                if let (Some(rank), Some(queue_size)) = (self.rank, self.queue_size) {
+                    write!(f, ": {}/{}", rank + 1, queue_size)?;
+                }
+            }
+            ProgressPhase::Processing => {
+                write!(f, "Processing")?;
+                if let Some(level) = self.levels.last() {
+                    match level.length {
+                        Some(length) => {
+                            write!(f, ": {}/{} {}", level.index + 1, length, level.unit)?
+                        }
+                        None => write!(f, ": {} {}", level.index + 1, level.unit)?,
+                    }
+                    if let Some(desc) = &level.desc {
+                        write!(f, " ({})", desc)?;
+                    }
+                }
+                if let Some(fraction) = self.fraction() {
+                    write!(f, " [{:.0}%]", fraction * 100.0)?;
+                }
+            }
+            ProgressPhase::Completed => write!(f, "Completed")?,
+            ProgressPhase::Failed => write!(f, "Failed")?,
+        }
+        if let Some(remaining) = self.remaining() {
+            write!(f, ", ETA {:.1}s", remaining.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+fn seconds(value: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{ProgressPhase, ProgressState};
+    use crate::structs::QueueDataMessage;
+
+    fn message(value: serde_json::Value) -> QueueDataMessage {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn tracks_queue_then_processing() {
+        let mut state = ProgressState::new();
+        state.apply(&message(json!({
+            "msg": "estimation", "event_id": "evt", "rank": 2, "queue_size": 5, "rank_eta": 3.0
+        })));
+        assert_eq!(state.phase, ProgressPhase::Queued);
+        assert_eq!(state.rank, Some(2));
+        assert!(state.to_string().starts_with("Queueing: 3/5"));
+
+        state.apply(&message(
+            json!({"msg": "process_starts", "event_id": "evt", "eta": 10.0}),
+        ));
+        assert_eq!(state.phase, ProgressPhase::Processing);
+        assert!(state.processing_started_at.is_some());
+        assert_eq!(state.rank, None);
+    }
+
+    #[test]
+    fn combines_nested_progress_levels() {
+        let mut state = ProgressState::new();
+        state.apply(&message(json!({
+            "msg": "progress",
+            "event_id": "evt",
+            "progress_data": [
+                {"index": 1, "length": 4, "unit": "images", "progress": null, "desc": null},
+                {"index": 5, "length": 10, "unit": "steps", "progress": null, "desc": null}
+            ]
+        })));
+
+        assert_eq!(state.fraction(), Some(0.375));
+        assert_eq!(state.to_string(), "Processing: 6/10 steps [38%]");
+    }
+
+    #[test]
+    fn indeterminate_progress_does_not_panic() {
+        let mut state = ProgressState::new();
+        state.apply(&message(json!({
+            "msg": "progress",
+            "event_id": "evt",
+            "progress_data": [
+                {"index": 3, "length": null, "unit": "steps", "progress": null, "desc": "loading"}
+            ]
+        })));
+
+        assert_eq!(state.fraction(), None);
+        assert_eq!(state.to_string(), "Processing: 4 steps (loading)");
+    }
+}
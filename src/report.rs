@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use crate::structs::{QueueDataMessage, QueueDataMessageOutput};
+
+/// When each step of a prediction happened, to tell whether time went into
+/// uploading, queueing or processing.
+#[derive(Clone, Debug)]
+pub struct PredictionReport {
+    pub route: Option<String>,
+    pub submitted_at: Instant,
+    /// When all input files were uploaded
+    pub uploaded_at: Option<Instant>,
+    pub queue_joined_at: Option<Instant>,
+    pub first_estimation_at: Option<Instant>,
+    pub process_started_at: Option<Instant>,
+    /// When the first `process_generating` or `process_streaming` message arrived
+    pub first_chunk_at: Option<Instant>,
+    pub completed_at: Option<Instant>,
+    /// Whether the app reported success on completion
+    pub success: Option<bool>,
+    /// The processing time reported by the server
+    pub server_duration: Option<Duration>,
+}
+
+impl PredictionReport {
+    pub fn new() -> Self {
+        Self {
+            route: None,
+            submitted_at: Instant::now(),
+            uploaded_at: None,
+            queue_joined_at: None,
+            first_estimation_at: None,
+            process_started_at: None,
+            first_chunk_at: None,
+            completed_at: None,
+            success: None,
+            server_duration: None,
+        }
+    }
+
+    /// Record the time of a queue message, returns whether the prediction completed
+    pub fn apply(&mut self, message: &QueueDataMessage) -> bool {
+        let now = Instant::now();
+        match message {
+            QueueDataMessage::Estimation { .. } => {
+                self.first_estimation_at.get_or_insert(now);
+            }
+            QueueDataMessage::ProcessStarts { .. } => {
+                self.process_started_at.get_or_insert(now);
+            }
+            QueueDataMessage::ProcessGenerating { .. }
+            | QueueDataMessage::ProcessStreaming { .. } => {
+                self.process_started_at.get_or_insert(now);
+                self.first_chunk_at.get_or_insert(now);
+            }
+            QueueDataMessage::ProcessCompleted {
+                output, success, ..
+            } => {
+                self.completed_at = Some(now);
+                self.success = Some(*success);
+                let duration = match output {
+                    QueueDataMessageOutput::Success { duration, .. }
+                    | QueueDataMessageOutput::Error { duration, .. } => *duration,
+                };
+                self.server_duration =
+                    duration.and_then(|duration| Duration::try_from_secs_f64(duration).ok());
+                return true;
+            }
+            QueueDataMessage::UnexpectedError { .. } => {
+                self.completed_at = Some(now);
+                self.success = Some(false);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Time spent uploading input files
+    pub fn upload_time(&self) -> Option<Duration> {
+        self.uploaded_at
+            .map(|uploaded| uploaded.duration_since(self.submitted_at))
+    }
+
+    /// Time between joining the queue and the start of processing
+    pub fn queue_time(&self) -> Option<Duration> {
+        Some(
+            self.process_started_at?
+                .duration_since(self.queue_joined_at?),
+        )
+    }
+
+    /// Time between the start of processing and completion
+    pub fn processing_time(&self) -> Option<Duration> {
+        Some(self.completed_at?.duration_since(self.process_started_at?))
+    }
+
+    /// Time from submission to the first generated output
+    pub fn time_to_first_chunk(&self) -> Option<Duration> {
+        self.first_chunk_at
+            .map(|first| first.duration_since(self.submitted_at))
+    }
+
+    /// Time from submission to completion
+    pub fn total_time(&self) -> Option<Duration> {
+        self.completed_at
+            .map(|completed| completed.duration_since(self.submitted_at))
+    }
+
+    /// Export the timings as `metrics` histograms and a prediction counter
+    #[cfg(feature = "metrics")]
+    pub(crate) fn export(&self) {
+        let route = self.route.clone().unwrap_or_default();
+        let outcome = if self.success == Some(true) {
+            "success"
+        } else {
+            "failure"
+        };
+        metrics::counter!(
+            "gradio_predictions_total",
+            "route" => route.clone(),
+            "outcome" => outcome
+        )
+        .increment(1);
+
+        let timings = [
+            ("gradio_upload_seconds", self.upload_time()),
+            ("gradio_queue_seconds", self.queue_time()),
+            ("gradio_processing_seconds", self.processing_time()),
+            ("gradio_first_chunk_seconds", self.time_to_first_chunk()),
+            ("gradio_prediction_seconds", self.total_time()),
+            ("gradio_server_duration_seconds", self.server_duration),
+        ];
+        for (name, duration) in timings {
+            if let Some(duration) = duration {
+                metrics::histogram!(name, "route" => route.clone()).record(duration.as_secs_f64());
+            }
+        }
+    }
+}
+
+impl Default for PredictionReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::PredictionReport;
+    use crate::structs::QueueDataMessage;
+
+    fn message(value: serde_json::Value) -> QueueDataMessage {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn records_phase_timestamps() {
+        let mut report = PredictionReport::new();
+        report.queue_joined_at = Some(report.submitted_at);
+
+        assert!(!report.apply(&message(json!({
+            "msg": "estimation", "event_id": "evt", "rank": 0, "queue_size": 1, "rank_eta": null
+        }))));
+        assert!(!report.apply(&message(json!({
+            "msg": "process_generating",
+            "event_id": "evt",
+            "output": {"data": ["Hel"]},
+            "success": true
+        }))));
+        assert!(report.apply(&message(json!({
+            "msg": "process_completed",
+            "event_id": "evt",
+            "output": {"data": ["Hello"], "duration": 0.25},
+            "success": true
+        }))));
+
+        assert!(report.first_estimation_at.is_some());
+        assert_eq!(report.process_started_at, report.first_chunk_at);
+        assert!(report.queue_time().is_some());
+        assert!(report.processing_time().is_some());
+        assert_eq!(report.success, Some(true));
+        assert_eq!(report.server_duration.unwrap().as_millis(), 250);
+    }
+}
@@ -0,0 +1,145 @@
+use futures_util::stream::StreamExt;
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest_eventsource::EventSource;
+
+use crate::{
+    client::{Client, SubmitOptions},
+    data::{PredictionInput, PredictionOutput},
+    stream::PredictionStream,
+    Result,
+};
+
+const HEARTBEAT_RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub(crate) fn generate_session_hash() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(char::from)
+        .collect()
+}
+
+/// Holds `/heartbeat/{session_hash}` open in the background.
+///
+/// Apps with `connect_heartbeat` enabled clean up session state and fire their
+/// unload events once the heartbeat connection goes away. The background task is
+/// stopped when the `Heartbeat` is dropped.
+#[derive(Debug)]
+pub struct Heartbeat {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Heartbeat {
+    /// Start the heartbeat, must be called within a Tokio runtime
+    pub fn start(http_client: &reqwest::Client, api_root: &str, session_hash: &str) -> Self {
+        let http_client = http_client.clone();
+        let url = format!("{}/heartbeat/{}", api_root, session_hash);
+
+        let task = tokio::spawn(async move {
+            loop {
+                // the request builder is always cloneable since it has no streaming body
+                if let Ok(mut es) = EventSource::new(http_client.get(&url)) {
+                    // the event source reconnects on its own, we only drain it
+                    while es.next().await.is_some() {}
+                }
+                tokio::time::sleep(HEARTBEAT_RECONNECT_DELAY).await;
+            }
+        });
+
+        Self { task }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// A handle that pins every submission to one `session_hash`.
+///
+/// Predictions made through a `Session` share server-side state such as `gr.State`
+/// values. If the app requests it, the session keeps a [`Heartbeat`] alive until it
+/// is dropped. Submissions on the same session should be awaited one after another,
+/// since the server delivers all messages of a session through a single stream.
+#[derive(Debug)]
+pub struct Session {
+    client: Client,
+    session_hash: String,
+    heartbeat: Option<Heartbeat>,
+}
+
+impl Session {
+    /// Create a new session, must be called within a Tokio runtime
+    pub fn new(client: &Client) -> Self {
+        let session_hash = generate_session_hash();
+        let heartbeat =
+            if client.view_config().connect_heartbeat == Some(true) && !client.is_replaying() {
+                Some(Heartbeat::start(
+                    &client.http_client,
+                    &client.api_root,
+                    &session_hash,
+                ))
+            } else {
+                None
+            };
+
+        Self {
+            client: client.clone(),
+            session_hash,
+            heartbeat,
+        }
+    }
+
+    pub fn session_hash(&self) -> &str {
+        &self.session_hash
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Whether a heartbeat is being maintained for this session
+    pub fn has_heartbeat(&self) -> bool {
+        self.heartbeat.is_some()
+    }
+
+    pub async fn submit(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+    ) -> Result<PredictionStream> {
+        self.submit_with_options(route, data, SubmitOptions::default())
+            .await
+    }
+
+    pub async fn submit_with_options(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+        options: SubmitOptions,
+    ) -> Result<PredictionStream> {
+        self.client
+            .submit_in_session(route, data, self.session_hash.clone(), &options)
+            .await
+    }
+
+    pub async fn predict(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+    ) -> Result<Vec<PredictionOutput>> {
+        let mut stream = self.submit(route, data).await?;
+        Client::wait_for_outputs(&mut stream).await
+    }
+
+    pub async fn predict_with_options(
+        &self,
+        route: &str,
+        data: Vec<PredictionInput>,
+        options: SubmitOptions,
+    ) -> Result<Vec<PredictionOutput>> {
+        let mut stream = self.submit_with_options(route, data, options).await?;
+        Client::wait_for_outputs(&mut stream).await
+    }
+}
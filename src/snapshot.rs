@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    structs::{ApiInfo, AppConfig},
+    Error, Result,
+};
+
+/// The resolved state of a [`Client`](crate::Client), saved to skip Hub
+/// resolution, wake-up and the `/config` and `/info` requests on startup.
+///
+/// Take one with [`Client::snapshot`](crate::Client::snapshot) and restore it with
+/// [`Client::from_snapshot`](crate::Client::from_snapshot).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClientSnapshot {
+    pub app_reference: String,
+    pub api_root: String,
+    pub space_id: Option<String>,
+    pub config: AppConfig,
+    pub api_info: ApiInfo,
+}
+
+impl ClientSnapshot {
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = tokio::fs::read(path).await?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+/// The API of an app read from a saved JSON file, with what the file tells
+/// about where the app is served
+#[derive(Clone, Debug)]
+pub struct SavedApi {
+    pub api_info: ApiInfo,
+    /// The Space ID or URL, from `app_reference` or `space_id`
+    pub app_reference: Option<String>,
+    pub api_root: Option<String>,
+    /// The Gradio version of the app
+    pub version: Option<String>,
+}
+
+impl SavedApi {
+    /// Read an [`ApiInfo`] as served by `/info`, or a file embedding one as
+    /// `api_info`, such as a [`ClientSnapshot`], cassette or lock file.
+    ///
+    /// Reads synchronously, so it also works in build scripts.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut value: serde_json::Value = serde_json::from_slice(&std::fs::read(path)?)?;
+        let app_reference = ["app_reference", "space_id"]
+            .iter()
+            .find_map(|key| value.get(key)?.as_str().map(str::to_string));
+        let api_root = value
+            .get("api_root")
+            .and_then(|root| root.as_str())
+            .map(str::to_string);
+        let version = value
+            .pointer("/config/version")
+            .or(value.get("version"))
+            .and_then(|version| version.as_str())
+            .map(str::to_string);
+
+        let api_info = if value.get("named_endpoints").is_some() {
+            value
+        } else {
+            value
+                .get_mut("api_info")
+                .map(serde_json::Value::take)
+                .ok_or_else(|| Error::NoApiInfoInFile {
+                    path: path.to_path_buf(),
+                })?
+        };
+        Ok(Self {
+            api_info: serde_json::from_value(api_info)?,
+            app_reference,
+            api_root,
+            version,
+        })
+    }
+}
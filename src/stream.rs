@@ -1,12 +1,57 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use futures_util::stream::StreamExt;
-use rand::{distributions::Alphanumeric, Rng};
 use reqwest_eventsource::{Event, EventSource};
+use tokio::sync::{mpsc, watch};
 
 use crate::{
-    structs::{QueueDataMessage, QueueDataMessageOutput, QueueJoinResponse},
-    Error, Result,
+    cassette::{FrameLog, RecordedPrediction},
+    client::AppState,
+    progress::{ProgressPhase, ProgressState},
+    report::PredictionReport,
+    session::generate_session_hash,
+    structs::{LogMessage, QueueDataMessage, QueueDataMessageOutput, QueueJoinResponse},
+    trace::{debug, info_span, Span},
+    Error, Result, TimeoutPhase,
 };
 
+/// How a [`PredictionStream`] reconnects to the queue after a network error
+#[derive(Clone, Debug)]
+pub struct ReconnectPolicy {
+    pub max_retries: usize,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Never reconnect, surface the first connection error
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    fn delay(&self, retry: usize) -> Duration {
+        let factor = 2u32.saturating_pow(retry.min(16) as u32);
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
 pub struct PredictionStream {
     pub es: EventSource,
     pub http_client: reqwest::Client,
@@ -16,6 +61,26 @@ pub struct PredictionStream {
     pub fn_index: i64,
     protocol: String,
     pending_diff_streams: Option<Vec<serde_json::Value>>,
+    app_state: Option<Arc<AppState>>,
+    log_sender: Option<mpsc::UnboundedSender<LogMessage>>,
+    progress: watch::Sender<ProgressState>,
+    queue_timeout: Option<Duration>,
+    processing_timeout: Option<Duration>,
+    reconnect: ReconnectPolicy,
+    retries: usize,
+    /// Reconnected and waiting for the first message. Gradio pops messages off
+    /// the session queue as it sends them and never resends, so a repeated frame
+    /// after a reconnect is a new message and only the `Open` is skipped
+    resuming: bool,
+    closed: bool,
+    frame_log: Option<FrameLog>,
+    replay: Option<VecDeque<String>>,
+    span: Span,
+    /// Span of the current queue or processing phase, and the phase it traces
+    phase_span: (ProgressPhase, Span),
+    report: PredictionReport,
+    /// Positions of state outputs, removed from the output data
+    skipped_outputs: Vec<usize>,
 }
 
 impl PredictionStream {
@@ -25,17 +90,29 @@ impl PredictionStream {
         protocol: &str,
         fn_index: impl Into<i64>,
         data: Vec<serde_json::Value>,
+    ) -> Result<Self> {
+        Self::join(
+            http_client,
+            api_root,
+            protocol,
+            fn_index.into(),
+            data,
+            generate_session_hash(),
+        )
+        .await
+    }
+
+    pub(crate) async fn join(
+        http_client: &reqwest::Client,
+        api_root: &str,
+        protocol: &str,
+        fn_index: i64,
+        data: Vec<serde_json::Value>,
+        session_hash: String,
     ) -> Result<Self> {
         let http_client = http_client.clone();
-        let fn_index = fn_index.into();
         let api_root = api_root.to_string();
 
-        let session_hash: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(10)
-            .map(char::from)
-            .collect();
-
         let url = format!("{}/queue/join", api_root);
         let payload = serde_json::json!({
             "fn_index": fn_index,
@@ -47,12 +124,52 @@ impl PredictionStream {
             return Err(Error::CannotJoinTaskQueue);
         }
         let res = res.json::<QueueJoinResponse>().await?;
-        let event_id = res.event_id;
+        let es = Self::connect(&http_client, &api_root, &session_hash)?;
 
-        let url = format!("{}/queue/data?session_hash={}", api_root, session_hash);
-        let es = EventSource::new(http_client.get(url))?;
+        Ok(Self::with_source(
+            es,
+            http_client,
+            api_root,
+            protocol,
+            fn_index,
+            session_hash,
+            res.event_id,
+        ))
+    }
+
+    /// A stream that plays back recorded frames instead of connecting to the queue
+    pub(crate) fn replay(
+        http_client: &reqwest::Client,
+        api_root: &str,
+        protocol: &str,
+        recorded: RecordedPrediction,
+        session_hash: String,
+    ) -> Result<Self> {
+        // event sources connect lazily, this one is never polled
+        let es = Self::connect(http_client, api_root, &session_hash)?;
+        let mut stream = Self::with_source(
+            es,
+            http_client.clone(),
+            api_root.to_string(),
+            protocol,
+            recorded.fn_index,
+            session_hash,
+            recorded.event_id,
+        );
+        stream.replay = Some(recorded.frames.into());
+        Ok(stream)
+    }
 
-        Ok(Self {
+    fn with_source(
+        es: EventSource,
+        http_client: reqwest::Client,
+        api_root: String,
+        protocol: &str,
+        fn_index: i64,
+        session_hash: String,
+        event_id: String,
+    ) -> Self {
+        Self {
             es,
             http_client,
             api_root,
@@ -61,46 +178,301 @@ impl PredictionStream {
             fn_index,
             protocol: protocol.to_string(),
             pending_diff_streams: None,
-        })
+            app_state: None,
+            log_sender: None,
+            progress: watch::Sender::new(ProgressState::new()),
+            queue_timeout: None,
+            processing_timeout: None,
+            reconnect: ReconnectPolicy::default(),
+            retries: 0,
+            resuming: false,
+            closed: false,
+            frame_log: None,
+            replay: None,
+            span: Span::none(),
+            phase_span: (ProgressPhase::Queued, Span::none()),
+            report: PredictionReport::new(),
+            skipped_outputs: vec![],
+        }
     }
 
     pub async fn next(&mut self) -> Option<Result<QueueDataMessage>> {
-        let event = self.es.next().await;
-        if event.is_none() {
-            return Some(Err(Error::StreamEnded));
+        if let Some(frames) = &mut self.replay {
+            // like a live stream, reading past the last frame is an error
+            let Some(data) = frames.pop_front() else {
+                return Some(Err(Error::StreamEnded));
+            };
+            return Some(self.handle_message(data));
         }
-        let event = event.unwrap();
-
-        match event {
-            Ok(Event::Open) => Some(Ok(QueueDataMessage::Open)),
-            Ok(Event::Message(message)) => match serde_json::from_str(&message.data) {
-                Ok(mut queue_message) => {
-                    if let Err(err) = normalize_queue_message(
-                        &self.protocol,
-                        &mut self.pending_diff_streams,
-                        &mut queue_message,
-                    ) {
-                        return Some(Err(err));
-                    }
 
-                    if matches!(queue_message, QueueDataMessage::CloseStream) {
-                        self.es.close();
+        loop {
+            let event = match self.deadline() {
+                Some((deadline, phase)) => {
+                    match tokio::time::timeout_at(deadline.into(), self.es.next()).await {
+                        Ok(event) => event,
+                        Err(_) => {
+                            let _ = self.cancel().await;
+                            return Some(Err(Error::Timeout { phase }));
+                        }
                     }
+                }
+                None => self.es.next().await,
+            };
+
+            let event = match event {
+                Some(event) => event,
+                None if self.is_finished() => return Some(Err(Error::StreamEnded)),
+                None => match self.reconnect().await {
+                    Ok(true) => continue,
+                    Ok(false) => return Some(Err(Error::StreamEnded)),
+                    Err(err) => return Some(Err(err)),
+                },
+            };
 
-                    Some(Ok(queue_message))
+            match event {
+                Ok(Event::Open) if self.resuming => {}
+                Ok(Event::Open) => return Some(Ok(QueueDataMessage::Open)),
+                Ok(Event::Message(message)) => {
+                    self.resuming = false;
+                    if let Some(frame_log) = &self.frame_log {
+                        frame_log
+                            .lock()
+                            .expect("frame log lock poisoned")
+                            .push(message.data.clone());
+                    }
+                    self.retries = 0;
+                    return Some(self.handle_message(message.data));
+                }
+                // the queue answers 404 for sessions it does not know
+                Err(reqwest_eventsource::Error::InvalidStatusCode(status, response))
+                    if status == reqwest::StatusCode::NOT_FOUND
+                        && response.url().path().ends_with("/queue/data") =>
+                {
+                    self.es.close();
+                    return Some(Err(Error::SessionNotFound));
+                }
+                Err(reqwest_eventsource::Error::StreamEnded) if self.is_finished() => {
+                    return Some(Err(Error::StreamEnded));
                 }
-                Err(err) => Some(Err(Error::ServerProtocol {
+                Err(err) => match self.reconnect().await {
+                    Ok(true) => continue,
+                    Ok(false) => {
+                        return Some(Err(Error::ClientProtocol {
+                            message: format!("{:#?}", err),
+                        }))
+                    }
+                    Err(err) => return Some(Err(err)),
+                },
+            }
+        }
+    }
+
+    fn handle_message(&mut self, data: String) -> Result<QueueDataMessage> {
+        let mut queue_message = match serde_json::from_str(&data) {
+            Ok(queue_message) => queue_message,
+            Err(err) => {
+                return Err(Error::ServerProtocol {
                     message: format!("{:#?}", err),
-                })),
-            },
-            Err(err) => Some(Err(Error::ClientProtocol {
-                message: format!("{:#?}", err),
-            })),
+                })
+            }
+        };
+
+        if let QueueDataMessage::UnexpectedError {
+            session_not_found: Some(true),
+            ..
+        } = queue_message
+        {
+            self.es.close();
+            return Err(Error::SessionNotFound);
+        }
+
+        normalize_queue_message(
+            &self.protocol,
+            &mut self.pending_diff_streams,
+            &mut queue_message,
+        )?;
+        remove_outputs(&mut queue_message, &self.skipped_outputs);
+        self.observe(&queue_message)?;
+
+        if matches!(queue_message, QueueDataMessage::CloseStream) {
+            self.closed = true;
+            self.es.close();
+        }
+
+        Ok(queue_message)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.closed
+            || matches!(
+                self.progress.borrow().phase,
+                ProgressPhase::Completed | ProgressPhase::Failed
+            )
+    }
+
+    /// Re-subscribe to the queue with backoff, returns `false` once retries are exhausted
+    async fn reconnect(&mut self) -> Result<bool> {
+        if self.closed || self.retries >= self.reconnect.max_retries {
+            return Ok(false);
+        }
+
+        let delay = self.reconnect.delay(self.retries);
+        self.retries += 1;
+        debug!(
+            parent: &self.span,
+            "Reconnecting to the queue in {:?}, attempt {}",
+            delay,
+            self.retries
+        );
+        tokio::time::sleep(delay).await;
+
+        self.es = Self::connect(&self.http_client, &self.api_root, &self.session_hash)?;
+        self.resuming = true;
+        Ok(true)
+    }
+
+    fn connect(
+        http_client: &reqwest::Client,
+        api_root: &str,
+        session_hash: &str,
+    ) -> Result<EventSource> {
+        let url = format!("{}/queue/data?session_hash={}", api_root, session_hash);
+        let mut es = EventSource::new(http_client.get(url))?;
+        // reconnection is handled by the stream to back off and give up on its own
+        es.set_retry_policy(Box::new(reqwest_eventsource::retry::Never));
+        Ok(es)
+    }
+
+    /// Set how the stream reconnects when the connection to the queue drops
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect = policy;
+    }
+
+    /// Receive the `gr.Info`, `gr.Warning` and `print` messages of this prediction.
+    ///
+    /// Messages are forwarded as the stream is polled with [`PredictionStream::next`].
+    /// Subscribing again replaces the previous receiver.
+    pub fn subscribe_logs(&mut self) -> mpsc::UnboundedReceiver<LogMessage> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.log_sender = Some(sender);
+        receiver
+    }
+
+    /// Watch the aggregated queue and processing progress of this prediction.
+    ///
+    /// The state is updated as the stream is polled with [`PredictionStream::next`].
+    pub fn progress(&self) -> watch::Receiver<ProgressState> {
+        self.progress.subscribe()
+    }
+
+    /// Get the current progress of this prediction
+    pub fn progress_state(&self) -> ProgressState {
+        self.progress.borrow().clone()
+    }
+
+    /// Bound the time spent waiting in the queue and processing, counted from
+    /// submission and from the start of processing respectively. When a budget
+    /// runs out the remote job is cancelled and [`Error::Timeout`] is returned.
+    pub fn set_timeouts(
+        &mut self,
+        queue_timeout: Option<Duration>,
+        processing_timeout: Option<Duration>,
+    ) {
+        self.queue_timeout = queue_timeout;
+        self.processing_timeout = processing_timeout;
+    }
+
+    fn deadline(&self) -> Option<(Instant, TimeoutPhase)> {
+        let state = self.progress.borrow();
+        match state.phase {
+            ProgressPhase::Queued => self
+                .queue_timeout
+                .map(|timeout| (state.submitted_at + timeout, TimeoutPhase::Queue)),
+            ProgressPhase::Processing => {
+                let started = state.processing_started_at?;
+                self.processing_timeout
+                    .map(|timeout| (started + timeout, TimeoutPhase::Processing))
+            }
+            ProgressPhase::Completed | ProgressPhase::Failed => None,
+        }
+    }
+
+    pub(crate) fn set_skipped_outputs(&mut self, positions: Vec<usize>) {
+        self.skipped_outputs = positions;
+    }
+
+    /// Record every received frame into `frame_log`
+    pub(crate) fn set_frame_log(&mut self, frame_log: FrameLog) {
+        self.frame_log = Some(frame_log);
+    }
+
+    /// Timestamps of the steps of this prediction so far
+    pub fn report(&self) -> &PredictionReport {
+        &self.report
+    }
+
+    pub(crate) fn set_report(&mut self, report: PredictionReport) {
+        self.report = report;
+    }
+
+    /// Trace the queue wait and processing of this prediction under `span`
+    pub(crate) fn set_span(&mut self, span: Span) {
+        self.phase_span = (
+            ProgressPhase::Queued,
+            info_span!(parent: &span, "queue_wait"),
+        );
+        self.span = span;
+    }
+
+    fn trace_phase(&mut self) {
+        let phase = self.progress.borrow().phase;
+        if phase == self.phase_span.0 {
+            return;
+        }
+        let span = match phase {
+            ProgressPhase::Processing => info_span!(parent: &self.span, "processing"),
+            _ => Span::none(),
+        };
+        self.phase_span = (phase, span);
+    }
+
+    pub(crate) fn set_app_state(&mut self, app_state: Arc<AppState>) {
+        self.app_state = Some(app_state);
+    }
+
+    fn observe(&mut self, message: &QueueDataMessage) -> Result<()> {
+        if self.progress.send_if_modified(|state| state.apply(message)) {
+            self.trace_phase();
+        }
+        if self.report.apply(message) {
+            #[cfg(feature = "metrics")]
+            self.report.export();
+        }
+
+        if let (Some(sender), Some(log)) = (&self.log_sender, message.as_log()) {
+            let _ = sender.send(log);
         }
+
+        let output = match message {
+            QueueDataMessage::ProcessGenerating { output, .. }
+            | QueueDataMessage::ProcessStreaming { output, .. }
+            | QueueDataMessage::ProcessCompleted { output, .. } => output,
+            _ => return Ok(()),
+        };
+
+        if let Some(app_state) = &self.app_state {
+            app_state.apply_output(output)?;
+        }
+
+        Ok(())
     }
 
     pub async fn cancel(&mut self) -> Result<()> {
+        self.closed = true;
         self.es.close();
+        if self.replay.is_some() {
+            return Ok(());
+        }
 
         let url = format!("{}/cancel", self.api_root);
         let payload = serde_json::json!({
@@ -127,14 +499,16 @@ fn normalize_queue_message(
 ) -> Result<()> {
     match message {
         QueueDataMessage::ProcessGenerating {
-            output, success, ..
+            output,
+            success: true,
+            ..
         }
         | QueueDataMessage::ProcessStreaming {
-            output, success, ..
+            output,
+            success: true,
+            ..
         } => {
-            if *success {
-                normalize_diff_output(protocol, pending_diff_streams, output)?;
-            }
+            normalize_diff_output(protocol, pending_diff_streams, output)?;
         }
         QueueDataMessage::ProcessCompleted { .. }
         | QueueDataMessage::UnexpectedError { .. }
@@ -147,6 +521,26 @@ fn normalize_queue_message(
     Ok(())
 }
 
+fn remove_outputs(message: &mut QueueDataMessage, positions: &[usize]) {
+    if positions.is_empty() {
+        return;
+    }
+    let output = match message {
+        QueueDataMessage::ProcessGenerating { output, .. }
+        | QueueDataMessage::ProcessStreaming { output, .. }
+        | QueueDataMessage::ProcessCompleted { output, .. } => output,
+        _ => return,
+    };
+    let Some(data) = output.data_mut() else {
+        return;
+    };
+    for &position in positions.iter().rev() {
+        if position < data.len() {
+            data.remove(position);
+        }
+    }
+}
+
 fn normalize_diff_output(
     protocol: &str,
     pending_diff_streams: &mut Option<Vec<serde_json::Value>>,
@@ -380,7 +774,10 @@ fn append_value(target: &mut serde_json::Value, value: serde_json::Value) -> Res
 mod tests {
     use serde_json::json;
 
-    use super::{apply_diff, normalize_queue_message};
+    use std::time::Duration;
+
+    use super::{apply_diff, normalize_queue_message, PredictionStream, ReconnectPolicy};
+    use crate::progress::ProgressPhase;
     use crate::structs::{QueueDataMessage, QueueDataMessageOutput};
 
     #[test]
@@ -450,6 +847,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reconnect_delay_backs_off_up_to_max() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.delay(0), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(10), Duration::from_secs(10));
+    }
+
     #[test]
     fn clears_pending_diffs_when_stream_closes() {
         let mut pending = Some(vec![json!("Hello")]);
@@ -459,4 +864,94 @@ mod tests {
 
         assert!(pending.is_none());
     }
+
+    #[test]
+    fn only_enforces_deadlines_set_by_the_caller() {
+        let http_client = reqwest::Client::new();
+        let es = PredictionStream::connect(&http_client, "http://localhost", "hash").unwrap();
+        let mut stream = PredictionStream::with_source(
+            es,
+            http_client,
+            "http://localhost".to_string(),
+            "sse_v3",
+            0,
+            "hash".to_string(),
+            "evt".to_string(),
+        );
+        stream.progress.send_modify(|state| {
+            state.phase = ProgressPhase::Processing;
+            state.processing_started_at = Some(state.submitted_at);
+            state.time_limit = Some(Duration::from_secs(1));
+        });
+        assert!(stream.deadline().is_none());
+
+        stream.set_timeouts(None, Some(Duration::from_secs(5)));
+        assert!(stream.deadline().is_some());
+    }
+
+    /// Serve `/queue/data` from a socket, one connection per body, closing each after its body
+    async fn serve_queue(bodies: Vec<String>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for body in bodies {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 4096];
+                let _ = socket.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn delivers_repeated_frames_after_a_reconnect() {
+        const ESTIMATION: &str =
+            "data: {\"msg\":\"estimation\",\"event_id\":\"evt\",\"rank\":0,\"queue_size\":1,\"rank_eta\":null}\n\n";
+        const COMPLETED: &str = "data: {\"msg\":\"process_completed\",\"event_id\":\"evt\",\"output\":{\"data\":[\"Hello\"]},\"success\":true}\n\n";
+        let api_root = serve_queue(vec![
+            ESTIMATION.to_string(),
+            format!("{}{}", ESTIMATION, COMPLETED),
+        ])
+        .await;
+
+        let http_client = reqwest::Client::new();
+        let es = PredictionStream::connect(&http_client, &api_root, "hash").unwrap();
+        let mut stream = PredictionStream::with_source(
+            es,
+            http_client,
+            api_root,
+            "sse_v3",
+            0,
+            "hash".to_string(),
+            "evt".to_string(),
+        );
+        stream.set_reconnect_policy(ReconnectPolicy {
+            max_retries: 1,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(10),
+        });
+
+        let mut messages = vec![];
+        while let Some(message) = stream.next().await {
+            match message.unwrap() {
+                QueueDataMessage::Open => messages.push("open"),
+                QueueDataMessage::Estimation { .. } => messages.push("estimation"),
+                QueueDataMessage::ProcessCompleted { .. } => {
+                    messages.push("completed");
+                    break;
+                }
+                other => panic!("unexpected message {:?}", other),
+            }
+        }
+        // the server never resends, so the second estimation is a new one
+        assert_eq!(messages, ["open", "estimation", "estimation", "completed"]);
+    }
 }
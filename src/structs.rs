@@ -72,19 +72,55 @@ pub struct Dependency {
     #[serde(default = "default_id")]
     pub id: i64,
     pub queue: Option<bool>,
+    #[serde(default)]
+    pub rendered_in: Option<i64>,
+    #[serde(default)]
+    pub batch: Option<bool>,
+    #[serde(default)]
+    pub max_batch_size: Option<i64>,
+    /// Ids of the input components
+    #[serde(default)]
+    pub inputs: Vec<i64>,
+    /// Ids of the output components
+    #[serde(default)]
+    pub outputs: Vec<i64>,
+}
+
+impl Dependency {
+    /// Whether the function was declared with `batch=True`
+    pub fn is_batched(&self) -> bool {
+        self.batch == Some(true)
+    }
+
+    /// The largest batch the server accepts, Gradio defaults to 4
+    pub fn batch_size(&self) -> Option<usize> {
+        self.is_batched()
+            .then(|| self.max_batch_size.unwrap_or(4).max(1) as usize)
+    }
 }
 
 fn default_id() -> i64 {
     -1
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum StringOrI64 {
     String(String),
     I64(i64),
 }
 
+/// The partial config sent by the server after a `gr.render` block re-renders
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RenderConfig {
+    #[serde(default)]
+    pub components: Vec<ComponentMeta>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    pub layout: Option<serde_json::Value>,
+    pub render_id: Option<i64>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ApiInfo {
     pub named_endpoints: HashMap<String, EndpointInfo>,
@@ -115,6 +151,9 @@ pub struct ApiDataType {
     pub r#type: String,
     #[serde(default)]
     pub description: String,
+    /// The rest of the JSON schema, such as `enum`, `items` or `properties`
+    #[serde(flatten)]
+    pub schema: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -163,6 +202,11 @@ pub enum QueueDataMessage {
     #[serde(rename = "log")]
     Log {
         event_id: Option<String>,
+        log: Option<String>,
+        level: Option<LogLevel>,
+        title: Option<String>,
+        duration: Option<f64>,
+        visible: Option<bool>,
     },
     #[serde(rename = "progress")]
     Progress {
@@ -209,6 +253,36 @@ pub enum QueueDataMessageOutput {
     },
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum LogLevel {
+    #[serde(rename = "info")]
+    Info,
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "success")]
+    Success,
+    #[serde(rename = "error")]
+    Error,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+/// A message logged by the app with `gr.Info`, `gr.Warning` or `print`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogMessage {
+    pub log: String,
+    pub level: LogLevel,
+    pub title: Option<String>,
+    pub duration: Option<f64>,
+    pub visible: Option<bool>,
+}
+
+impl LogMessage {
+    pub fn is_warning(&self) -> bool {
+        self.level == LogLevel::Warning
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ProcessingProgressData {
     pub index: usize,
@@ -218,6 +292,158 @@ pub struct ProcessingProgressData {
     pub desc: Option<String>,
 }
 
+impl AppConfig {
+    /// Merge a `gr.render` update into the component and dependency tables.
+    ///
+    /// Components are replaced by id. Dependencies previously rendered by the same
+    /// render block are dropped before the new ones are appended.
+    pub fn apply_render_config(&mut self, render: &RenderConfig) {
+        for component in &render.components {
+            match self.components.iter_mut().find(|c| c.id == component.id) {
+                Some(existing) => *existing = component.clone(),
+                None => self.components.push(component.clone()),
+            }
+        }
+
+        let new_ids: Vec<i64> = render
+            .dependencies
+            .iter()
+            .map(|d| d.id)
+            .filter(|id| *id != -1)
+            .collect();
+        self.dependencies.retain(|d| {
+            let stale = render.render_id.is_some() && d.rendered_in == render.render_id;
+            !stale && !new_ids.contains(&d.id)
+        });
+        self.dependencies
+            .extend(render.dependencies.iter().cloned());
+    }
+
+    /// Positions of `gr.State` components among the component `ids` of a dependency.
+    ///
+    /// State values are kept by the server, so they are left out of the API info.
+    pub fn state_positions(&self, ids: &[i64]) -> Vec<usize> {
+        ids.iter()
+            .enumerate()
+            .filter(|(_, id)| {
+                self.components.iter().any(|component| {
+                    component.id == StringOrI64::I64(**id) && component.r#type == "state"
+                })
+            })
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// The `file_types` accepted by each non-state input among the component `ids`
+    /// of a dependency, `None` when a component accepts any file
+    pub fn accepted_file_types(&self, ids: &[i64]) -> Vec<Option<Vec<String>>> {
+        ids.iter()
+            .filter_map(|id| {
+                let component = self
+                    .components
+                    .iter()
+                    .find(|component| component.id == StringOrI64::I64(*id));
+                match component {
+                    Some(component) if component.r#type == "state" => None,
+                    Some(component) => Some(
+                        serde_json::from_value(component.props["file_types"].clone())
+                            .unwrap_or_default(),
+                    ),
+                    None => Some(None),
+                }
+            })
+            .collect()
+    }
+}
+
+impl ApiData {
+    /// The parameter name, falling back to the label
+    pub fn name(&self) -> Option<&str> {
+        self.parameter_name.as_deref().or(self.label.as_deref())
+    }
+
+    /// Whether the value is a file, given as a path or URL
+    pub fn is_file(&self) -> bool {
+        self.python_type.r#type == "filepath" || is_file_data(self.r#type.schema.get("properties"))
+    }
+
+    /// Whether the value is a list of files, such as `gr.File(file_count="multiple")`
+    pub fn is_file_list(&self) -> bool {
+        self.python_type.r#type == "List[filepath]"
+            || (self.r#type.r#type == "array"
+                && is_file_data(
+                    self.r#type
+                        .schema
+                        .get("items")
+                        .and_then(|items| items.get("properties")),
+                ))
+    }
+
+    /// A JSON schema of the value, with files described as a path or URL string
+    pub fn json_schema(&self) -> serde_json::Value {
+        let mut schema = if self.is_file() {
+            serde_json::Map::from_iter([
+                ("type".to_string(), "string".into()),
+                (
+                    "description".to_string(),
+                    "Local path or URL of a file".into(),
+                ),
+            ])
+        } else {
+            let mut schema = self.r#type.schema.clone();
+            schema.insert("type".to_string(), self.r#type.r#type.clone().into());
+            let description = if self.r#type.description.is_empty() {
+                &self.python_type.description
+            } else {
+                &self.r#type.description
+            };
+            if !description.is_empty() {
+                schema.insert("description".to_string(), description.clone().into());
+            }
+            schema
+        };
+
+        if let Some(label) = &self.label {
+            schema.insert("title".to_string(), label.clone().into());
+        }
+        if self.parameter_has_default == Some(true) {
+            let default = self.parameter_default.clone().unwrap_or_default();
+            schema.insert("default".to_string(), default);
+        }
+        serde_json::Value::Object(schema)
+    }
+}
+
+/// Whether the `properties` of a JSON schema are those of a `FileData`
+fn is_file_data(properties: Option<&serde_json::Value>) -> bool {
+    properties.is_some_and(|properties| {
+        properties.get("path").is_some() && properties.get("meta").is_some()
+    })
+}
+
+impl QueueDataMessage {
+    /// Get the structured log message, if this is a `log` message
+    pub fn as_log(&self) -> Option<LogMessage> {
+        match self {
+            Self::Log {
+                log,
+                level,
+                title,
+                duration,
+                visible,
+                ..
+            } => Some(LogMessage {
+                log: log.clone().unwrap_or_default(),
+                level: level.clone().unwrap_or(LogLevel::Info),
+                title: title.clone(),
+                duration: *duration,
+                visible: *visible,
+            }),
+            _ => None,
+        }
+    }
+}
+
 impl QueueDataMessageOutput {
     pub fn data_mut(&mut self) -> Option<&mut Vec<serde_json::Value>> {
         match self {
@@ -226,3 +452,175 @@ impl QueueDataMessageOutput {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{ApiData, AppConfig, LogLevel, QueueDataMessage, RenderConfig};
+
+    fn config() -> AppConfig {
+        serde_json::from_value(json!({
+            "components": [
+                {"type": "textbox", "id": 1, "props": {}},
+                {"type": "button", "id": 2, "props": {}}
+            ],
+            "dependencies": [
+                {"api_name": "predict", "id": 0},
+                {"api_name": "old_rendered", "id": 5, "rendered_in": 3}
+            ],
+            "mode": "blocks",
+            "root": "https://example.com",
+            "theme": "default",
+            "title": "Gradio",
+            "version": "5.0.0",
+            "protocol": "sse_v3",
+            "layout": {},
+            "auth_message": null,
+            "css": null,
+            "js": null,
+            "head": null,
+            "root_url": null,
+            "space_id": null,
+            "stylesheets": [],
+            "path": null,
+            "theme_hash": null,
+            "username": null,
+            "max_file_size": null,
+            "api_prefix": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn render_config_replaces_components_and_rendered_dependencies() {
+        let mut config = config();
+        let render: RenderConfig = serde_json::from_value(json!({
+            "components": [
+                {"type": "textbox", "id": 2, "props": {"label": "replaced"}},
+                {"type": "button", "id": 7, "props": {}}
+            ],
+            "dependencies": [{"api_name": "new_rendered", "id": 6, "rendered_in": 3}],
+            "layout": {},
+            "render_id": 3
+        }))
+        .unwrap();
+
+        config.apply_render_config(&render);
+
+        let types: Vec<&str> = config
+            .components
+            .iter()
+            .map(|c| c.r#type.as_str())
+            .collect();
+        assert_eq!(types, vec!["textbox", "textbox", "button"]);
+        let names: Vec<&str> = config
+            .dependencies
+            .iter()
+            .map(|d| d.api_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["predict", "new_rendered"]);
+    }
+
+    #[test]
+    fn finds_state_positions() {
+        let mut config = config();
+        config
+            .components
+            .push(serde_json::from_value(json!({"type": "state", "id": 3, "props": {}})).unwrap());
+
+        assert_eq!(config.state_positions(&[1, 3, 2]), vec![1]);
+        assert!(config.state_positions(&[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn finds_accepted_file_types() {
+        let mut config = config();
+        config.components.extend([
+            serde_json::from_value(json!({"type": "state", "id": 3, "props": {}})).unwrap(),
+            serde_json::from_value(
+                json!({"type": "file", "id": 4, "props": {"file_types": ["image", ".pdf"]}}),
+            )
+            .unwrap(),
+        ]);
+
+        assert_eq!(
+            config.accepted_file_types(&[1, 3, 4]),
+            vec![None, Some(vec!["image".to_string(), ".pdf".to_string()])]
+        );
+    }
+
+    #[test]
+    fn derives_json_schemas_of_parameters() {
+        let slider: ApiData = serde_json::from_value(json!({
+            "label": "Steps",
+            "parameter_name": "steps",
+            "parameter_has_default": true,
+            "parameter_default": 28,
+            "component": "Slider",
+            "type": {"type": "number", "description": "numeric value between 1 and 50"},
+            "python_type": {"type": "float", "description": ""}
+        }))
+        .unwrap();
+        assert_eq!(
+            slider.json_schema(),
+            json!({
+                "type": "number",
+                "description": "numeric value between 1 and 50",
+                "title": "Steps",
+                "default": 28
+            })
+        );
+
+        let image: ApiData = serde_json::from_value(json!({
+            "label": "Image",
+            "parameter_name": "image",
+            "parameter_has_default": false,
+            "component": "Image",
+            "type": {
+                "type": "object",
+                "properties": {"path": {"type": "string"}, "meta": {"type": "object"}}
+            },
+            "python_type": {"type": "Dict(path: str)", "description": ""}
+        }))
+        .unwrap();
+        assert!(image.is_file());
+        assert_eq!(image.json_schema()["type"], "string");
+    }
+
+    #[test]
+    fn parses_warning_log_messages() {
+        let message: QueueDataMessage = serde_json::from_value(json!({
+            "msg": "log",
+            "event_id": "evt",
+            "log": "Input was truncated",
+            "level": "warning",
+            "title": "Warning",
+            "duration": 10.0,
+            "visible": true
+        }))
+        .unwrap();
+
+        let log = message.as_log().unwrap();
+        assert!(log.is_warning());
+        assert_eq!(log.log, "Input was truncated");
+        assert_eq!(log.title.as_deref(), Some("Warning"));
+        assert_eq!(log.duration, Some(10.0));
+    }
+
+    #[test]
+    fn keeps_unknown_log_levels() {
+        let message: QueueDataMessage = serde_json::from_value(json!({
+            "msg": "log",
+            "event_id": "evt",
+            "log": "hello",
+            "level": "debug"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            message.as_log().unwrap().level,
+            LogLevel::Unknown("debug".to_string())
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use crate::client::{Client, ClientOptions};
 use crate::data::{GradioFileData, PredictionInput, PredictionOutput};
+use crate::media::MediaStream;
+use crate::snapshot::ClientSnapshot;
 use crate::stream::PredictionStream;
 use crate::structs::QueueDataMessage;
 use crate::Result;
@@ -13,6 +15,16 @@ impl Client {
         Ok(client)
     }
 
+    pub fn from_snapshot_sync(
+        snapshot: ClientSnapshot,
+        options: ClientOptions,
+        revalidate: bool,
+    ) -> Result<Self> {
+        let rt = Runtime::new()?;
+        let client = rt.block_on(Client::from_snapshot(snapshot, options, revalidate))?;
+        Ok(client)
+    }
+
     pub fn submit_sync(
         &self,
         path: &str,
@@ -32,6 +44,19 @@ impl Client {
         let output = rt.block_on(self.predict(path, inputs))?;
         Ok(output)
     }
+
+    pub fn predict_many_sync<I>(
+        &self,
+        path: &str,
+        inputs: I,
+        concurrency: usize,
+    ) -> Result<Vec<Result<Vec<PredictionOutput>>>>
+    where
+        I: IntoIterator<Item = Vec<PredictionInput>>,
+    {
+        let rt = Runtime::new()?;
+        Ok(rt.block_on(self.predict_many(path, inputs, concurrency)))
+    }
 }
 
 impl GradioFileData {
@@ -62,3 +87,19 @@ impl PredictionStream {
         Some(output)
     }
 }
+
+impl MediaStream {
+    pub fn next_chunk_sync(&mut self) -> Option<Result<bytes::Bytes>> {
+        let rt = match Runtime::new() {
+            Ok(rt) => rt,
+            Err(err) => return Some(Err(err.into())),
+        };
+        rt.block_on(self.next_chunk())
+    }
+
+    pub fn save_to_path_sync(self, path: impl AsRef<Path>) -> Result<()> {
+        let rt = Runtime::new()?;
+        rt.block_on(self.save_to_path(path))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! Diagnostics through `tracing` when the `tracing` feature is enabled.
+//!
+//! Without the feature, spans compile to nothing and warnings are printed to
+//! stderr as before.
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::{debug, info_span, warn, Instrument, Span};
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) use shim::{debug, info_span, warn, Instrument, Span};
+
+#[cfg(not(feature = "tracing"))]
+mod shim {
+    #[derive(Clone, Debug, Default)]
+    pub(crate) struct Span;
+
+    impl Span {
+        pub(crate) fn none() -> Self {
+            Self
+        }
+
+        pub(crate) fn record<V>(&self, _field: &str, _value: V) -> &Self {
+            self
+        }
+    }
+
+    pub(crate) trait Instrument: Sized {
+        fn instrument(self, _span: Span) -> Self {
+            self
+        }
+    }
+
+    impl<F: std::future::Future> Instrument for F {}
+
+    macro_rules! info_span {
+        ($($tt:tt)*) => {
+            $crate::trace::Span::none()
+        };
+    }
+
+    macro_rules! warning {
+        ($($arg:tt)*) => {
+            eprintln!("[warning] {}", format_args!($($arg)*))
+        };
+    }
+
+    macro_rules! debug {
+        ($($tt:tt)*) => {};
+    }
+
+    pub(crate) use {debug, info_span, warning as warn};
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use gradio::{
+    codegen::{generate, CodegenOptions},
+    Cassette, Client, Error, SavedApi,
+};
+
+// only some of the generated methods are exercised
+#[allow(dead_code)]
+mod synthetic_app {
+    include!("fixtures/synthetic_app.rs");
+}
+
+// parameters named after the identifiers the generated methods use
+#[allow(dead_code)]
+mod reserved_names {
+    include!("fixtures/reserved_names.rs");
+}
+
+const CASSETTE_PATH: &str = "tests/fixtures/synthetic_app.json";
+const APP_REFERENCE: &str = "http://127.0.0.1:7860";
+
+#[tokio::test]
+async fn generated_client_is_up_to_date() -> Result<()> {
+    let cassette = Cassette::load(CASSETTE_PATH).await?;
+    let source = generate(
+        &cassette.api_info,
+        &CodegenOptions::new(APP_REFERENCE).with_struct_name("SyntheticApp"),
+    );
+    assert_eq!(
+        source,
+        std::fs::read_to_string("tests/fixtures/synthetic_app.rs")?
+    );
+    Ok(())
+}
+
+#[test]
+fn generated_client_renames_reserved_fields() -> Result<()> {
+    let saved = SavedApi::load("tests/fixtures/reserved_names.json")?;
+    let source = generate(
+        &saved.api_info,
+        &CodegenOptions::new(APP_REFERENCE).with_struct_name("ReservedNames"),
+    );
+    assert_eq!(
+        source,
+        std::fs::read_to_string("tests/fixtures/reserved_names.rs")?
+    );
+
+    let options = reserved_names::PredictOptions::default();
+    assert_eq!(options.data_1, "");
+    Ok(())
+}
+
+#[tokio::test]
+async fn generated_client_calls_endpoints() -> Result<()> {
+    let cassette = Cassette::load(CASSETTE_PATH).await?;
+    let app = synthetic_app::SyntheticApp::from_client(Client::from_cassette(cassette)?);
+
+    assert_eq!(app.predict("Rust").await?.output_1, "Hello Rust!");
+    assert_eq!(
+        app.describe("tests/fixtures/sample.txt").await?.output_1,
+        "sample.txt: 27 bytes"
+    );
+    Ok(())
+}
+
+#[test]
+fn reads_the_api_of_saved_files() -> Result<()> {
+    let saved = SavedApi::load(CASSETTE_PATH)?;
+    assert!(saved.app_reference.is_none());
+    assert!(saved.api_info.named_endpoints.contains_key("/predict"));
+
+    let saved = SavedApi::load("tests/fixtures/spaces/hello_world.json")?;
+    assert_eq!(saved.app_reference.as_deref(), Some("gradio/hello_world"));
+
+    // a glTF scene is JSON, but not an API
+    let err = SavedApi::load("tests/fixtures/fox.gltf").unwrap_err();
+    assert!(matches!(err, Error::NoApiInfoInFile { .. }));
+    Ok(())
+}
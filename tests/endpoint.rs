@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use gradio::{Cassette, Client, PredictionOutput};
+use serde::Deserialize;
+
+#[gradio::endpoint(route = "/predict", output = Greeting)]
+struct Predict {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Greeting {
+    output: String,
+}
+
+#[gradio::endpoint(route = "describe", output = (String,))]
+struct Describe(PathBuf);
+
+#[gradio::endpoint(route = "/predict")]
+struct RawPredict<'a> {
+    name: &'a str,
+}
+
+async fn replay_client() -> Result<Client> {
+    let cassette = Cassette::load("tests/fixtures/synthetic_app.json").await?;
+    Ok(Client::from_cassette(cassette)?)
+}
+
+#[tokio::test]
+async fn calls_declared_endpoints() -> Result<()> {
+    let client = replay_client().await?;
+
+    let greeting = client
+        .call(Predict {
+            name: "Rust".to_string(),
+        })
+        .await?;
+    assert_eq!(greeting.output, "Hello Rust!");
+
+    let (description,) = client
+        .call(Describe("tests/fixtures/sample.txt".into()))
+        .await?;
+    assert_eq!(description, "sample.txt: 27 bytes");
+
+    let outputs: Vec<PredictionOutput> = client.call(RawPredict { name: "Jacob" }).await?;
+    assert_eq!(outputs[0].clone().as_value()?, "Hello Jacob!");
+    Ok(())
+}
@@ -0,0 +1,80 @@
+// Generated by `gr codegen` from the API info of http://127.0.0.1:7860, do not edit.
+
+use gradio::{Client, ClientOptions, PredictionInput, PredictionOutput, Result};
+
+/// Typed client for http://127.0.0.1:7860
+#[derive(Clone, Debug)]
+pub struct ReservedNames {
+    client: Client,
+}
+
+impl ReservedNames {
+    pub const APP_REFERENCE: &'static str = "http://127.0.0.1:7860";
+
+    pub async fn new(options: ClientOptions) -> Result<Self> {
+        let client = Client::new(Self::APP_REFERENCE, options).await?;
+        Ok(Self::from_client(client))
+    }
+
+    pub fn from_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Call `/predict`
+    ///
+    /// - `options_1`: Options (Textbox)
+    /// - `output_1`: Output (Textbox)
+    pub async fn predict(
+        &self,
+        options_1: impl Into<String>,
+        output_1: impl Into<String>,
+        options: PredictOptions,
+    ) -> Result<PredictOutput> {
+        let data = vec![
+            PredictionInput::from_value(options_1.into()),
+            PredictionInput::from_value(output_1.into()),
+            PredictionInput::from_value(options.data_1),
+        ];
+        let outputs = self.client.predict("/predict", data).await?;
+        Ok(PredictOutput {
+            outputs_1: output(&outputs, 0)?,
+        })
+    }
+}
+
+/// Optional parameters of `/predict`, defaulting to the values of the app
+#[derive(Clone, Debug)]
+pub struct PredictOptions {
+    /// Data (Textbox)
+    pub data_1: String,
+}
+
+impl Default for PredictOptions {
+    fn default() -> Self {
+        Self {
+            data_1: "".to_string(),
+        }
+    }
+}
+
+/// Outputs of `/predict`
+#[derive(Clone, Debug)]
+pub struct PredictOutput {
+    /// outputs (Textbox)
+    pub outputs_1: String,
+}
+
+fn output<T: gradio::serde::de::DeserializeOwned>(
+    outputs: &[PredictionOutput],
+    index: usize,
+) -> Result<T> {
+    outputs
+        .get(index)
+        .cloned()
+        .ok_or(gradio::Error::MissingOutput { index })?
+        .decode()
+}
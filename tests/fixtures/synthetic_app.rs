@@ -0,0 +1,91 @@
+// Generated by `gr codegen` from the API info of http://127.0.0.1:7860, do not edit.
+
+use gradio::{Client, ClientOptions, PredictionInput, PredictionOutput, Result};
+
+/// Typed client for http://127.0.0.1:7860
+#[derive(Clone, Debug)]
+pub struct SyntheticApp {
+    client: Client,
+}
+
+impl SyntheticApp {
+    pub const APP_REFERENCE: &'static str = "http://127.0.0.1:7860";
+
+    pub async fn new(options: ClientOptions) -> Result<Self> {
+        let client = Client::new(Self::APP_REFERENCE, options).await?;
+        Ok(Self::from_client(client))
+    }
+
+    pub fn from_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Call `/describe`
+    ///
+    /// - `file`: file (File)
+    pub async fn describe(&self, file: impl Into<std::path::PathBuf>) -> Result<DescribeOutput> {
+        let data = vec![PredictionInput::from_file(file.into())];
+        let outputs = self.client.predict("/describe", data).await?;
+        Ok(DescribeOutput {
+            output_1: output(&outputs, 0)?,
+        })
+    }
+
+    /// Call `/predict`
+    ///
+    /// - `name`: name (Textbox)
+    pub async fn predict(&self, name: impl Into<String>) -> Result<PredictOutput> {
+        let data = vec![PredictionInput::from_value(name.into())];
+        let outputs = self.client.predict("/predict", data).await?;
+        Ok(PredictOutput {
+            output_1: output(&outputs, 0)?,
+        })
+    }
+
+    /// Call `/stream`
+    ///
+    /// - `name`: name (Textbox)
+    pub async fn stream(&self, name: impl Into<String>) -> Result<StreamOutput> {
+        let data = vec![PredictionInput::from_value(name.into())];
+        let outputs = self.client.predict("/stream", data).await?;
+        Ok(StreamOutput {
+            output_1: output(&outputs, 0)?,
+        })
+    }
+}
+
+/// Outputs of `/describe`
+#[derive(Clone, Debug)]
+pub struct DescribeOutput {
+    /// output (Textbox)
+    pub output_1: String,
+}
+
+/// Outputs of `/predict`
+#[derive(Clone, Debug)]
+pub struct PredictOutput {
+    /// output (Textbox)
+    pub output_1: String,
+}
+
+/// Outputs of `/stream`
+#[derive(Clone, Debug)]
+pub struct StreamOutput {
+    /// output (Textbox)
+    pub output_1: String,
+}
+
+fn output<T: gradio::serde::de::DeserializeOwned>(
+    outputs: &[PredictionOutput],
+    index: usize,
+) -> Result<T> {
+    outputs
+        .get(index)
+        .cloned()
+        .ok_or(gradio::Error::MissingOutput { index })?
+        .decode()
+}
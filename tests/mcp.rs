@@ -0,0 +1,105 @@
+use anyhow::Result;
+use gradio::{mcp::McpServer, Cassette, Client};
+use serde_json::{json, Value};
+
+async fn replay_server() -> Result<McpServer> {
+    let cassette = Cassette::load("tests/fixtures/synthetic_app.json").await?;
+    Ok(
+        McpServer::new(Client::from_cassette(cassette)?, "synthetic_app")
+            .with_allowed_dir("tests/fixtures"),
+    )
+}
+
+async fn request(server: &McpServer, method: &str, params: Value) -> Value {
+    server
+        .handle(json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+        .await
+        .expect("requests get a response")
+}
+
+#[tokio::test]
+async fn lists_endpoints_as_tools() -> Result<()> {
+    let server = replay_server().await?;
+
+    let response = request(
+        &server,
+        "initialize",
+        json!({"protocolVersion": "2025-03-26"}),
+    )
+    .await;
+    assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+    assert!(server
+        .handle(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+        .await
+        .is_none());
+
+    let response = request(&server, "tools/list", json!({})).await;
+    let tools = response["result"]["tools"].as_array().unwrap();
+    let names: Vec<&str> = tools.iter().filter_map(|t| t["name"].as_str()).collect();
+    assert_eq!(names, ["describe", "predict", "stream"]);
+    assert_eq!(
+        tools[0]["inputSchema"]["properties"]["file"]["type"],
+        "string"
+    );
+    assert_eq!(tools[1]["inputSchema"]["required"], json!(["name"]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn calls_tools_with_values_and_files() -> Result<()> {
+    let server = replay_server().await?;
+
+    let response = request(
+        &server,
+        "tools/call",
+        json!({"name": "predict", "arguments": {"name": "Rust"}}),
+    )
+    .await;
+    assert_eq!(
+        response["result"]["content"],
+        json!([{"type": "text", "text": "Hello Rust!"}])
+    );
+
+    let response = request(
+        &server,
+        "tools/call",
+        json!({"name": "describe", "arguments": {"file": "tests/fixtures/sample.txt"}}),
+    )
+    .await;
+    assert_eq!(
+        response["result"]["content"][0]["text"],
+        "sample.txt: 27 bytes"
+    );
+
+    for outside in ["Cargo.toml", "tests/fixtures/../../Cargo.toml"] {
+        let response = request(
+            &server,
+            "tools/call",
+            json!({"name": "describe", "arguments": {"file": outside}}),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], true);
+    }
+
+    let response = request(&server, "tools/call", json!({"name": "predict"})).await;
+    assert_eq!(response["result"]["isError"], true);
+
+    let response = request(&server, "tools/call", json!({"name": "missing"})).await;
+    assert_eq!(response["error"]["code"], -32602);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejects_local_files_without_an_allowed_dir() -> Result<()> {
+    let cassette = Cassette::load("tests/fixtures/synthetic_app.json").await?;
+    let server = McpServer::new(Client::from_cassette(cassette)?, "synthetic_app");
+
+    let response = request(
+        &server,
+        "tools/call",
+        json!({"name": "describe", "arguments": {"file": "tests/fixtures/sample.txt"}}),
+    )
+    .await;
+    assert_eq!(response["result"]["isError"], true);
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use gradio::{
+    structs::QueueDataMessage, Cassette, Client, ClientOptions, ClientPool, ClientSnapshot,
+    PredictionInput, PredictionStream, RoutingStrategy,
+};
+
+const CASSETTE_PATH: &str = "tests/fixtures/synthetic_app.json";
+
+/// A replay of the synthetic app, told apart from the others by its API root
+async fn replica(api_root: &str) -> Result<Client> {
+    let mut cassette = Cassette::load(CASSETTE_PATH).await?;
+    cassette.api_root = api_root.to_string();
+    Ok(Client::from_cassette(cassette)?)
+}
+
+/// A replica with the same API whose server is down
+async fn unreachable_replica() -> Result<Client> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let api_root = format!("http://{}", listener.local_addr()?);
+    drop(listener);
+
+    let cassette = Cassette::load(CASSETTE_PATH).await?;
+    let snapshot = ClientSnapshot {
+        app_reference: api_root.clone(),
+        api_root,
+        space_id: None,
+        config: cassette.config,
+        api_info: cassette.api_info,
+    };
+    Ok(Client::from_snapshot(snapshot, ClientOptions::default(), false).await?)
+}
+
+async fn greet(pool: &ClientPool, name: &str) -> Result<PredictionStream> {
+    Ok(pool
+        .submit("/predict", vec![PredictionInput::from_value(name)])
+        .await?)
+}
+
+async fn complete(stream: &mut PredictionStream) -> Result<()> {
+    while let Some(message) = stream.next().await {
+        if let QueueDataMessage::ProcessCompleted { .. } = message? {
+            return Ok(());
+        }
+    }
+    anyhow::bail!("the prediction did not complete")
+}
+
+#[tokio::test]
+async fn round_robin_cycles_through_replicas() -> Result<()> {
+    let pool =
+        ClientPool::from_clients(vec![replica("http://a").await?, replica("http://b").await?])?
+            .with_strategy(RoutingStrategy::RoundRobin);
+
+    assert_eq!(greet(&pool, "Rust").await?.api_root, "http://a");
+    assert_eq!(greet(&pool, "Jacob").await?.api_root, "http://b");
+    assert_eq!(greet(&pool, "Jacob").await?.api_root, "http://a");
+    Ok(())
+}
+
+#[tokio::test]
+async fn least_queue_prefers_shorter_queues() -> Result<()> {
+    let pool =
+        ClientPool::from_clients(vec![replica("http://a").await?, replica("http://b").await?])?;
+
+    let mut first = greet(&pool, "Rust").await?;
+    assert_eq!(first.api_root, "http://a");
+    // a has a prediction in flight, b none
+    let second = greet(&pool, "Rust").await?;
+    assert_eq!(second.api_root, "http://b");
+
+    // a reported a queue of 2 while waiting, longer than the one prediction on b
+    complete(&mut first).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(greet(&pool, "Jacob").await?.api_root, "http://b");
+    Ok(())
+}
+
+#[tokio::test]
+async fn fails_over_from_unreachable_replicas() -> Result<()> {
+    let pool = ClientPool::from_clients(vec![
+        unreachable_replica().await?,
+        replica("http://a").await?,
+    ])?
+    .with_strategy(RoutingStrategy::RoundRobin);
+
+    let output = pool
+        .predict("/predict", vec![PredictionInput::from_value("Rust")])
+        .await?;
+    assert_eq!(output[0].clone().as_value()?, "Hello Rust!");
+    // the unreachable replica is skipped while it backs off, even on its turn
+    assert_eq!(greet(&pool, "Jacob").await?.api_root, "http://a");
+    Ok(())
+}
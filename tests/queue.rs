@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use gradio::{Cassette, Client, ClientOptions, ClientSnapshot, PredictionInput};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Predictions joined and not completed yet, and the most seen at once
+#[derive(Debug, Default)]
+struct Load {
+    in_flight: AtomicUsize,
+    max: AtomicUsize,
+}
+
+/// Names sent to the queue, by session hash
+type Names = Arc<Mutex<HashMap<String, String>>>;
+
+/// A queue greeting the number it is sent, taking that many tens of
+/// milliseconds so that predictions complete out of order
+async fn serve_queue(load: Arc<Load>) -> Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let api_root = format!("http://{}", listener.local_addr()?);
+    let names = Names::default();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            tokio::spawn(handle(socket, load.clone(), names.clone()));
+        }
+    });
+    Ok(api_root)
+}
+
+async fn handle(socket: TcpStream, load: Arc<Load>, names: Names) -> Result<()> {
+    let mut reader = BufReader::new(socket);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            length = value.trim().parse()?;
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (content_type, body) = if path.ends_with("/queue/join") {
+        let join: Value = serde_json::from_slice(&body)?;
+        let session_hash = join["session_hash"].as_str().unwrap_or_default();
+        let name = join["data"][0].as_str().unwrap_or_default();
+        names
+            .lock()
+            .unwrap()
+            .insert(session_hash.to_string(), name.to_string());
+        let in_flight = load.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        load.max.fetch_max(in_flight, Ordering::SeqCst);
+        ("application/json", json!({"event_id": "evt"}).to_string())
+    } else {
+        let session_hash = path.rsplit("session_hash=").next().unwrap_or_default();
+        let name = names
+            .lock()
+            .unwrap()
+            .remove(session_hash)
+            .unwrap_or_default();
+        tokio::time::sleep(Duration::from_millis(10 * name.parse::<u64>()?)).await;
+        load.in_flight.fetch_sub(1, Ordering::SeqCst);
+        let completed = json!({
+            "msg": "process_completed",
+            "event_id": "evt",
+            "output": {"data": [format!("Hello {}!", name)]},
+            "success": true,
+        });
+        ("text/event-stream", format!("data: {}\n\n", completed))
+    };
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        content_type,
+        body.len(),
+        body
+    );
+    reader.into_inner().write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn predict_many_bounds_concurrency() -> Result<()> {
+    let load = Arc::new(Load::default());
+    let api_root = serve_queue(load.clone()).await?;
+    let cassette = Cassette::load("tests/fixtures/synthetic_app.json").await?;
+    let snapshot = ClientSnapshot {
+        app_reference: api_root.clone(),
+        api_root,
+        space_id: None,
+        config: cassette.config,
+        api_info: cassette.api_info,
+    };
+    let client = Client::from_snapshot(snapshot, ClientOptions::default(), false).await?;
+
+    let names = ["6", "5", "4", "3", "2", "1"];
+    let inputs = names.map(|name| vec![PredictionInput::from_value(name)]);
+    let results = client.predict_many("/predict", inputs, 2).await;
+
+    assert_eq!(load.max.load(Ordering::SeqCst), 2);
+    for (name, result) in names.iter().zip(results) {
+        assert_eq!(result?[0].clone().as_value()?, format!("Hello {}!", name));
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use gradio::{structs::QueueDataMessage, Cassette, ChatSession, Client, Error, PredictionInput};
+
+const CASSETTE_PATH: &str = "tests/fixtures/synthetic_app.json";
+const SAMPLE_FILE_PATH: &str = "tests/fixtures/sample.txt";
+const BATCHED_CASSETTE_PATH: &str = "tests/fixtures/batched.json";
+const CHAT_STATE_CASSETTE_PATH: &str = "tests/fixtures/chat_state.json";
+
+async fn replay_client() -> Result<Client> {
+    let cassette = Cassette::load(CASSETTE_PATH).await?;
+    Ok(Client::from_cassette(cassette)?)
+}
+
+#[tokio::test]
+async fn replays_predictions_offline() -> Result<()> {
+    let client = replay_client().await?;
+    assert!(client.is_replaying());
+    assert!(client.view_api().named_endpoints.contains_key("/predict"));
+
+    let output = client
+        .predict("/predict", vec![PredictionInput::from_value("Rust")])
+        .await?;
+    assert_eq!(output[0].clone().as_value()?.as_str(), Some("Hello Rust!"));
+
+    let output = client
+        .predict("/predict", vec![PredictionInput::from_value("Jacob")])
+        .await?;
+    assert_eq!(output[0].clone().as_value()?.as_str(), Some("Hello Jacob!"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn replays_diff_streams_through_normalization() -> Result<()> {
+    let client = replay_client().await?;
+    let mut stream = client
+        .submit("/stream", vec![PredictionInput::from_value("Rust")])
+        .await?;
+
+    let mut generated = vec![];
+    while let Some(message) = stream.next().await {
+        match message? {
+            QueueDataMessage::ProcessGenerating { output, .. } => {
+                let outputs: Vec<gradio::PredictionOutput> = output.try_into()?;
+                generated.push(outputs[0].clone().as_value()?);
+            }
+            QueueDataMessage::CloseStream => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(generated, ["Hel", "Hello ", "Hello Rust!"]);
+    // the recording is exhausted, as a live stream would be once closed
+    assert!(matches!(stream.next().await, Some(Err(Error::StreamEnded))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn replays_recorded_uploads() -> Result<()> {
+    let client = replay_client().await?;
+    let output = client
+        .predict(
+            "/describe",
+            vec![PredictionInput::from_file(SAMPLE_FILE_PATH)],
+        )
+        .await?;
+
+    assert_eq!(
+        output[0].clone().as_value()?.as_str(),
+        Some("sample.txt: 27 bytes")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn unrecorded_inputs_are_rejected() -> Result<()> {
+    let client = replay_client().await?;
+    let result = client
+        .predict("/predict", vec![PredictionInput::from_value("Ferris")])
+        .await;
+
+    assert!(matches!(result, Err(Error::CassetteMismatch { .. })));
+    Ok(())
+}
+
+#[tokio::test]
+async fn files_over_the_size_limit_are_rejected_before_upload() -> Result<()> {
+    let mut cassette = Cassette::load(CASSETTE_PATH).await?;
+    cassette.config.max_file_size = Some(10);
+    let client = Client::from_cassette(cassette)?;
+
+    let result = client
+        .predict(
+            "/describe",
+            vec![PredictionInput::from_file(SAMPLE_FILE_PATH)],
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(Error::FileTooLarge {
+            size: 27,
+            max_size: 10,
+            ..
+        })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn predict_many_keeps_the_order_of_inputs() -> Result<()> {
+    let client = replay_client().await?;
+
+    let inputs = ["Rust", "Unrecorded", "Jacob"]
+        .map(|name| vec![PredictionInput::from_value(name)])
+        .to_vec();
+    let results = client.predict_many("/predict", inputs, 2).await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[0].as_ref().unwrap()[0].clone().as_value()?,
+        "Hello Rust!"
+    );
+    // a failed prediction keeps its place and does not stop the others
+    assert!(matches!(results[1], Err(Error::CassetteMismatch { .. })));
+    assert_eq!(
+        results[2].as_ref().unwrap()[0].clone().as_value()?,
+        "Hello Jacob!"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn batched_endpoints_take_single_samples() -> Result<()> {
+    let client = Client::from_cassette(Cassette::load(BATCHED_CASSETTE_PATH).await?)?;
+    assert_eq!(client.batch_size("/predict")?, Some(4));
+
+    let output = client
+        .predict("/predict", vec![PredictionInput::from_value("a")])
+        .await?;
+    assert_eq!(output[0].clone().as_value()?, "Hello a!");
+    // a list with one item is an output of its own, not a batch of one
+    assert_eq!(output[1].clone().as_value()?, serde_json::json!(["a"]));
+
+    let outputs = client
+        .predict_batch(
+            "/predict",
+            vec![
+                vec![PredictionInput::from_value("b")],
+                vec![PredictionInput::from_value("c")],
+            ],
+        )
+        .await?;
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs[0][0].clone().as_value()?, "Hello b!");
+    assert_eq!(outputs[1][0].clone().as_value()?, "Hello c!");
+    Ok(())
+}
+
+#[tokio::test]
+async fn only_chat_sessions_fill_state_inputs() -> Result<()> {
+    let client = Client::from_cassette(Cassette::load(CHAT_STATE_CASSETTE_PATH).await?)?;
+
+    let reply = ChatSession::new(&client).send("Hello").await?;
+    assert_eq!(reply[0].text().as_deref(), Some("Hi there!"));
+
+    // other calls send and receive every input and output, state included
+    let output = client
+        .predict(
+            "/chat",
+            vec![
+                PredictionInput::from_value("Hello"),
+                PredictionInput::from_value(serde_json::json!({"turns": 1})),
+            ],
+        )
+        .await?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[1].clone().as_value()?["turns"], 2);
+    Ok(())
+}
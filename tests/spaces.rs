@@ -0,0 +1,70 @@
+//! Public Spaces replayed offline from the hand-written cassettes in
+//! `tests/fixtures/spaces`.
+
+use anyhow::Result;
+use gradio::{Cassette, Client, PredictionInput};
+
+const SAMPLE_AUDIO_PATH: &str = "tests/fixtures/audio_sample.wav";
+const SAMPLE_MODEL_PATH: &str = "tests/fixtures/fox.gltf";
+
+async fn replay_space(name: &str) -> Result<Client> {
+    let cassette = Cassette::load(format!("tests/fixtures/spaces/{}.json", name)).await?;
+    Ok(Client::from_cassette(cassette)?)
+}
+
+#[tokio::test]
+async fn gradio_6_space_predicts_successfully() -> Result<()> {
+    let client = replay_space("hello_world").await?;
+    assert_eq!(client.space_id.as_deref(), Some("gradio/hello_world"));
+
+    let output = client
+        .predict("/predict", vec![PredictionInput::from_value("Jacob")])
+        .await?;
+
+    assert_eq!(output[0].clone().as_value()?.as_str(), Some("Hello Jacob!"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn gradio_6_file_upload_prediction_works() -> Result<()> {
+    let client = replay_space("model3d").await?;
+    let output = client
+        .predict(
+            "/predict",
+            vec![PredictionInput::from_file(SAMPLE_MODEL_PATH)],
+        )
+        .await?;
+
+    let model = output[0].clone().as_file()?;
+    assert_eq!(model.meta._type, "gradio.FileData");
+    assert!(model.url.as_deref().is_some_and(|url| !url.is_empty()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn gradio_5_file_upload_prediction_works() -> Result<()> {
+    let client = replay_space("whisper").await?;
+    let output = client
+        .predict(
+            "/predict",
+            vec![
+                PredictionInput::from_file(SAMPLE_AUDIO_PATH),
+                PredictionInput::from_value("transcribe"),
+            ],
+        )
+        .await?;
+
+    let transcript = output[0].clone().as_value()?;
+    assert!(transcript.as_str().unwrap_or("").trim().len() > 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gradio_4_space_metadata_is_available() -> Result<()> {
+    let client = replay_space("vocal_separation").await?;
+    let api = client.view_api();
+
+    assert!(client.view_config().version.starts_with("4."));
+    assert!(api.named_endpoints.contains_key("/separate"));
+    Ok(())
+}